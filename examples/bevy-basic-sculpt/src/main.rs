@@ -27,9 +27,13 @@ fn main() {
             Box::new(SmoothDeformation::default()),
         ]))
         .init_resource::<CurrentDeformation>()
+        .insert_resource(BrushSize {
+            radius: 1.5,
+            falloff: 1.5,
+        })
         .insert_non_send_resource(AvailableSelections::new(vec![
-            Box::new(MetricWithFalloff::sphere(1.5, 1.5, SMOOTH_FALLOFF)),
-            Box::new(SurfaceMetricWithFalloff::sphere(1.5, 1.5, SMOOTH_FALLOFF)),
+            metric_selector as SelectorFn,
+            surface_metric_selector,
         ]))
         .init_resource::<CurrentSelection>()
         .add_plugins((
@@ -51,3 +55,15 @@ fn main() {
         )
         .run();
 }
+
+fn metric_selector(radius: f32, falloff: f32) -> Box<dyn MeshSelector> {
+    Box::new(MetricWithFalloff::sphere(radius, falloff, SMOOTH_FALLOFF))
+}
+
+fn surface_metric_selector(radius: f32, falloff: f32) -> Box<dyn MeshSelector> {
+    Box::new(SurfaceMetricWithFalloff::sphere(
+        radius,
+        falloff,
+        SMOOTH_FALLOFF,
+    ))
+}
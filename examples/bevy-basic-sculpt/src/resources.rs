@@ -1,5 +1,7 @@
 use bevy::prelude::*;
-use freestyle_sculpt::{deformation::DeformationField, selectors::MeshSelector};
+use freestyle_sculpt::{
+    deformation::DeformationField, selectors::MeshSelector, transform::MeshTransform,
+};
 
 #[derive(Resource, Copy, Clone, Default, Deref, DerefMut)]
 pub struct CurrentDeformation(usize);
@@ -16,11 +18,38 @@ impl AvailableDeformations {
 #[derive(Resource, Copy, Clone, Default, Deref, DerefMut)]
 pub struct CurrentSelection(usize);
 
+/// Creates a selector from the brush radius and falloff in the local space of the sculpted mesh.
+pub type SelectorFn = fn(f32, f32) -> Box<dyn MeshSelector>;
+
 #[derive(Deref, DerefMut)]
-pub struct AvailableSelections(Vec<Box<dyn MeshSelector>>);
+pub struct AvailableSelections(Vec<SelectorFn>);
 
 impl AvailableSelections {
-    pub fn new(selections: Vec<Box<dyn MeshSelector>>) -> Self {
+    pub fn new(selections: Vec<SelectorFn>) -> Self {
         Self(selections)
     }
 }
+
+/// The size of the brush in world units.
+///
+/// The selectors work in the local space of the mesh graph, so they are created from this
+/// with the transform of the sculpted entity.
+#[derive(Resource, Copy, Clone)]
+pub struct BrushSize {
+    pub radius: f32,
+    pub falloff: f32,
+}
+
+impl BrushSize {
+    /// Creates the selector `selector_fn` with this brush size converted to the local space of `transform`.
+    pub fn selector(
+        &self,
+        selector_fn: SelectorFn,
+        transform: &MeshTransform,
+    ) -> Box<dyn MeshSelector> {
+        selector_fn(
+            transform.length_to_local(self.radius),
+            transform.length_to_local(self.falloff),
+        )
+    }
+}
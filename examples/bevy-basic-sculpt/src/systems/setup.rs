@@ -1,5 +1,6 @@
 use bevy::{color::palettes::css::SILVER, prelude::*};
 use bevy_panorbit_camera::PanOrbitCamera;
+use freestyle_sculpt::attributes::MeshAttributes;
 use mesh_graph::{MeshGraph, primitives::IcoSphere};
 
 pub fn setup(
@@ -7,15 +8,30 @@ pub fn setup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    let (mesh, mesh_graph) = init_icosphere();
+    let material = materials.add(Color::from(SILVER));
 
-    commands.spawn((
-        Mesh3d(meshes.add(mesh)),
-        MeshMaterial3d(materials.add(Color::from(SILVER))),
-        mesh_graph,
-        Name::new("Icosphere"),
-        Transform::default(),
-    ));
+    // every mesh graph is sculpted in its local space, so the objects can be placed freely
+    for (name, transform) in [
+        ("Icosphere", Transform::from_xyz(-4.0, 0.0, 0.0)),
+        (
+            "Scaled Icosphere",
+            Transform::from_xyz(4.0, 0.0, 0.0)
+                .with_rotation(Quat::from_rotation_z(0.5))
+                .with_scale(Vec3::splat(0.75)),
+        ),
+    ] {
+        let (mesh, mesh_graph) = init_icosphere();
+
+        commands.spawn((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(material.clone()),
+            mesh_graph,
+            // the sculpt mask and feature edges are stored next to the mesh graph
+            MeshAttributes::default(),
+            Name::new(name),
+            transform,
+        ));
+    }
 
     commands.spawn((
         PointLight {
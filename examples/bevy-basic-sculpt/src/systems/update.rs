@@ -1,7 +1,9 @@
 use bevy::picking::backend::ray::RayMap;
 use bevy::prelude::*;
 use freestyle_sculpt::SculptParams;
+use freestyle_sculpt::attributes::MeshAttributes;
 use freestyle_sculpt::ray::Ray;
+use freestyle_sculpt::transform::MeshTransform;
 use mesh_graph::MeshGraph;
 
 use crate::resources::*;
@@ -35,15 +37,18 @@ pub fn handle_mouse(
     mut available_deformations: NonSendMut<AvailableDeformations>,
     current_selection: Res<CurrentSelection>,
     available_selections: NonSend<AvailableSelections>,
+    brush_size: Res<BrushSize>,
     picking_cameras: Query<&Camera>,
-    mut mesh_graphs: Query<(&mut MeshGraph, &Mesh3d)>,
+    mut mesh_graphs: Query<(
+        Entity,
+        &mut MeshGraph,
+        &mut MeshAttributes,
+        &Mesh3d,
+        &GlobalTransform,
+    )>,
     mut prev_point: Local<Vec3>,
-    mut deformation_active: Local<bool>,
+    mut active_entity: Local<Option<Entity>>,
 ) -> Result {
-    let (mut mesh_graph, mesh_handle) = mesh_graphs.single_mut()?;
-
-    let mesh = meshes.get_mut(mesh_handle).unwrap();
-
     let mut available_deformations = available_deformations.get_mut(**current_deformation);
     let deformation_field = available_deformations.as_mut().unwrap();
 
@@ -52,59 +57,97 @@ pub fn handle_mouse(
             continue;
         };
 
-        let selector = available_selections.get(**current_selection).unwrap();
+        let selector_fn = *available_selections.get(**current_selection).unwrap();
 
         let ray = Ray::from(ray);
-        let intersection = ray.cast_ray_and_get_face_id(&mesh_graph);
 
         if buttons.just_pressed(MouseButton::Left) {
-            // Mouse down
-            if let Some(intersection) = intersection {
-                deformation_field.on_pointer_down(&mesh_graph, selector.as_ref(), intersection);
-
-                *deformation_active = true;
-                *prev_point = intersection.point;
-            } else {
-                *deformation_active = false;
+            // Mouse down: sculpt the object that is hit first
+            let mut closest = None;
+            let mut min_distance = f32::MAX;
+
+            for (entity, mesh_graph, _, _, global_transform) in &mesh_graphs {
+                let transform = MeshTransform::from(global_transform);
+
+                if let Some(intersection) =
+                    ray.cast_ray_and_get_face_id_with_transform(&mesh_graph, &transform)
+                {
+                    let world_point = transform.point_to_world(intersection.point);
+                    let distance = world_point.distance_squared(ray.origin);
+
+                    if distance < min_distance {
+                        min_distance = distance;
+                        closest = Some((entity, intersection, world_point, transform));
+                    }
+                }
+            }
+
+            *active_entity = None;
+
+            if let Some((entity, intersection, world_point, transform)) = closest {
+                let (_, mesh_graph, attributes, _, _) = mesh_graphs.get(entity)?;
+                // the brush size is given in world units but the selectors work in local space
+                let selector = brush_size.selector(selector_fn, &transform);
+                deformation_field.on_pointer_down(
+                    &mesh_graph,
+                    selector.as_ref(),
+                    intersection,
+                    Some(attributes),
+                );
+
+                *active_entity = Some(entity);
+                *prev_point = world_point;
             }
         } else if buttons.just_released(MouseButton::Left) {
             // Mouse up
-            if *deformation_active {
+            if let Some(entity) = active_entity.take() {
+                let (_, mut mesh_graph, _, _, _) = mesh_graphs.get_mut(entity)?;
                 mesh_graph.optimize_bvh_incremental();
-
-                *deformation_active = false;
             }
-        } else {
+        } else if let Some(entity) = *active_entity {
             // Mouse move
-            if *deformation_active {
-                // o.z + d.z * t = p.z
-                // t = (p.z - o.z) / d.z
-                let cur_point = ray.point_at((prev_point.z - ray.origin.z) / ray.direction.z);
-
-                if prev_point.distance_squared(cur_point) > 0.001 {
-                    let mouse_translation = cur_point - *prev_point;
-
-                    if deformation_field.on_pointer_move(
-                        &mesh_graph,
-                        selector.as_ref(),
-                        mouse_translation,
-                        intersection,
-                    ) {
-                        let strength = if **current_deformation == 0 {
-                            1.0
-                        } else {
-                            0.01
-                        };
-                        deformation_field.apply(&mut mesh_graph, strength, *sculpt_params);
-
-                        *mesh = mesh_graph.clone().into();
-                    }
-
-                    *prev_point = cur_point;
+            let (_, mut mesh_graph, mut attributes, mesh_handle, global_transform) =
+                mesh_graphs.get_mut(entity)?;
+            let transform = MeshTransform::from(global_transform);
+
+            let intersection = ray.cast_ray_and_get_face_id_with_transform(&mesh_graph, &transform);
+
+            // o.z + d.z * t = p.z
+            // t = (p.z - o.z) / d.z
+            let cur_point = ray.point_at((prev_point.z - ray.origin.z) / ray.direction.z);
+
+            if prev_point.distance_squared(cur_point) > 0.001 {
+                // the deformation works in the local space of the mesh graph
+                let mouse_translation = transform.vector_to_local(cur_point - *prev_point);
+                let selector = brush_size.selector(selector_fn, &transform);
+
+                if deformation_field.on_pointer_move(
+                    &mesh_graph,
+                    selector.as_ref(),
+                    mouse_translation,
+                    intersection,
+                    Some(&*attributes),
+                ) {
+                    let strength = if **current_deformation == 0 {
+                        1.0
+                    } else {
+                        0.01
+                    };
+                    deformation_field.apply_with_attributes(
+                        &mut mesh_graph,
+                        &mut attributes,
+                        strength,
+                        sculpt_params.to_local(&transform),
+                    );
+
+                    let mesh = meshes.get_mut(mesh_handle).unwrap();
+                    *mesh = mesh_graph.clone().into();
                 }
 
-                mesh_graph.optimize_bvh_incremental();
+                *prev_point = cur_point;
             }
+
+            mesh_graph.optimize_bvh_incremental();
         }
     }
 
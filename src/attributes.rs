@@ -0,0 +1,284 @@
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{FaceId, HalfedgeId, MeshGraph, VertexId, error_none};
+use tracing::instrument;
+
+use crate::utils::face_area_normal;
+
+/// Additional data attached to the elements of a mesh graph.
+///
+/// Pass this to [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes)
+/// so that it's kept up to date when the dynamic topology splits and collapses edges.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshAttributes {
+    /// Halfedges of sharp edges and creases that are preserved by the dynamic topology.
+    ///
+    /// Always contains both halfedges of an edge. Use [`MeshAttributes::tag_feature_edge`]
+    /// and [`MeshAttributes::detect_feature_edges`] to fill it.
+    pub feature_edges: HashSet<HalfedgeId>,
+
+    /// Groups of faces, e.g. the parts of a multi-part sculpt. Faces without an entry are in face set 0.
+    ///
+    /// Faces that are created by splitting an edge are in the same face set as the face they were split from.
+    pub face_sets: HashMap<FaceId, u32>,
+
+    /// The sculpt mask from 0.0 (not masked) to 1.0 (fully masked). Vertices without an entry are not masked.
+    ///
+    /// The weights of all selections in [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes)
+    /// are multiplied by one minus the mask. Use [`MaskDeformation`](crate::deformation::MaskDeformation) to paint it.
+    pub mask: HashMap<VertexId, f32>,
+}
+
+impl MeshAttributes {
+    /// Returns `true` if there are no feature edges, face sets or masked vertices.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.feature_edges.is_empty() && self.face_sets.is_empty() && self.mask.is_empty()
+    }
+
+    /// Returns the face set of the given face.
+    #[inline]
+    pub fn face_set(&self, face_id: FaceId) -> u32 {
+        self.face_sets.get(&face_id).copied().unwrap_or_default()
+    }
+
+    /// Puts the given faces into the face set `face_set`.
+    pub fn assign_face_set(&mut self, face_ids: impl IntoIterator<Item = FaceId>, face_set: u32) {
+        for face_id in face_ids {
+            if face_set == 0 {
+                self.face_sets.remove(&face_id);
+            } else {
+                self.face_sets.insert(face_id, face_set);
+            }
+        }
+    }
+
+    /// Marks the edge of the given halfedge as a feature edge.
+    pub fn tag_feature_edge(&mut self, mesh_graph: &MeshGraph, halfedge_id: HalfedgeId) {
+        self.feature_edges.insert(halfedge_id);
+
+        if let Some(twin_id) = mesh_graph.halfedges.get(halfedge_id).and_then(|he| he.twin) {
+            self.feature_edges.insert(twin_id);
+        }
+    }
+
+    /// Removes the feature mark from the edge of the given halfedge.
+    pub fn untag_feature_edge(&mut self, mesh_graph: &MeshGraph, halfedge_id: HalfedgeId) {
+        self.feature_edges.remove(&halfedge_id);
+
+        if let Some(twin_id) = mesh_graph.halfedges.get(halfedge_id).and_then(|he| he.twin) {
+            self.feature_edges.remove(&twin_id);
+        }
+    }
+
+    /// Returns `true` if the edge of the given halfedge is a feature edge.
+    #[inline]
+    pub fn is_feature_edge(&self, halfedge_id: HalfedgeId) -> bool {
+        self.feature_edges.contains(&halfedge_id)
+    }
+
+    /// Tags all edges of the mesh graph whose dihedral angle is larger than `angle` (in radians)
+    /// as feature edges. Boundary edges are ignored.
+    ///
+    /// Returns the number of newly tagged edges.
+    #[instrument(skip(self, mesh_graph))]
+    pub fn detect_feature_edges(&mut self, mesh_graph: &MeshGraph, angle: f32) -> usize {
+        self.detect_feature_edges_of_halfedges(mesh_graph, angle, mesh_graph.halfedges.keys())
+    }
+
+    /// Same as [`MeshAttributes::detect_feature_edges`] but only checks the edges incident to the given vertices.
+    pub(crate) fn detect_feature_edges_around_vertices(
+        &mut self,
+        mesh_graph: &MeshGraph,
+        angle: f32,
+        vertex_ids: impl IntoIterator<Item = VertexId>,
+    ) -> usize {
+        let halfedge_ids = vertex_ids
+            .into_iter()
+            .filter_map(|vertex_id| mesh_graph.vertices.get(vertex_id))
+            .flat_map(|vertex| vertex.outgoing_halfedges(mesh_graph))
+            .collect::<Vec<_>>();
+
+        self.detect_feature_edges_of_halfedges(mesh_graph, angle, halfedge_ids)
+    }
+
+    fn detect_feature_edges_of_halfedges(
+        &mut self,
+        mesh_graph: &MeshGraph,
+        angle: f32,
+        halfedge_ids: impl IntoIterator<Item = HalfedgeId>,
+    ) -> usize {
+        let min_cos = angle.cos();
+        let mut count = 0;
+
+        for halfedge_id in halfedge_ids {
+            if self.feature_edges.contains(&halfedge_id) {
+                continue;
+            }
+
+            let Some(he) = mesh_graph
+                .halfedges
+                .get(halfedge_id)
+                .or_else(error_none!("Halfedge not found"))
+            else {
+                continue;
+            };
+
+            let Some(twin_id) = he.twin else {
+                continue;
+            };
+
+            let face_normals = he
+                .face
+                .zip(mesh_graph.halfedges.get(twin_id).and_then(|twin| twin.face));
+
+            let Some((normal, twin_normal)) = face_normals.and_then(|(face_id, twin_face_id)| {
+                let normal = face_area_normal(face_id, mesh_graph)?.try_normalize()?;
+                let twin_normal = face_area_normal(twin_face_id, mesh_graph)?.try_normalize()?;
+                Some((normal, twin_normal))
+            }) else {
+                continue;
+            };
+
+            if normal.dot(twin_normal) < min_cos {
+                self.feature_edges.insert(halfedge_id);
+                self.feature_edges.insert(twin_id);
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Number of feature edges that are incident to the given vertex.
+    ///
+    /// Vertices with exactly two feature edges lie on a crease. Any other non-zero number
+    /// means that the vertex is a corner or the end of a crease.
+    pub fn feature_edge_count(&self, mesh_graph: &MeshGraph, vertex_id: VertexId) -> usize {
+        if self.feature_edges.is_empty() {
+            return 0;
+        }
+
+        mesh_graph
+            .vertices
+            .get(vertex_id)
+            .map(|vertex| {
+                vertex
+                    .outgoing_halfedges(mesh_graph)
+                    .filter(|he_id| self.feature_edges.contains(he_id))
+                    .count()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Makes sure that both halfedges of every edge around `vertex_id` agree on being a feature edge.
+    ///
+    /// Collapsing edges merges pairs of edges into one, which might leave only one of the new twins tagged.
+    pub(crate) fn sync_feature_twins(&mut self, mesh_graph: &MeshGraph, vertex_id: VertexId) {
+        if self.feature_edges.is_empty() {
+            return;
+        }
+
+        let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+            return;
+        };
+
+        for he_id in vertex.outgoing_halfedges(mesh_graph).collect::<Vec<_>>() {
+            let Some(twin_id) = mesh_graph.halfedges.get(he_id).and_then(|he| he.twin) else {
+                continue;
+            };
+
+            if self.feature_edges.contains(&he_id) || self.feature_edges.contains(&twin_id) {
+                self.feature_edges.insert(he_id);
+                self.feature_edges.insert(twin_id);
+            }
+        }
+    }
+
+    /// Returns the mask value of the given vertex.
+    #[inline]
+    pub fn mask(&self, vertex_id: VertexId) -> f32 {
+        self.mask.get(&vertex_id).copied().unwrap_or_default()
+    }
+
+    /// Sets the mask value of the given vertex. The value is clamped to the range 0.0 to 1.0.
+    pub fn set_mask(&mut self, vertex_id: VertexId, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+
+        if value > 0.0 {
+            self.mask.insert(vertex_id, value);
+        } else {
+            self.mask.remove(&vertex_id);
+        }
+    }
+
+    /// Removes the mask from all vertices.
+    pub fn clear_mask(&mut self) {
+        self.mask.clear();
+    }
+
+    /// Masks everything that isn't masked and the other way around.
+    #[instrument(skip_all)]
+    pub fn invert_mask(&mut self, mesh_graph: &MeshGraph) {
+        for vertex_id in mesh_graph.vertices.keys() {
+            self.set_mask(vertex_id, 1.0 - self.mask(vertex_id));
+        }
+    }
+
+    /// Expands the mask by one ring of vertices.
+    #[instrument(skip_all)]
+    pub fn grow_mask(&mut self, mesh_graph: &MeshGraph) {
+        self.filter_mask(mesh_graph, |value, neighbours| {
+            neighbours.iter().copied().fold(value, f32::max)
+        });
+    }
+
+    /// Contracts the mask by one ring of vertices.
+    #[instrument(skip_all)]
+    pub fn shrink_mask(&mut self, mesh_graph: &MeshGraph) {
+        self.filter_mask(mesh_graph, |value, neighbours| {
+            neighbours.iter().copied().fold(value, f32::min)
+        });
+    }
+
+    /// Smooths the mask by averaging every vertex with its neighbours.
+    #[instrument(skip_all)]
+    pub fn blur_mask(&mut self, mesh_graph: &MeshGraph) {
+        self.filter_mask(mesh_graph, |value, neighbours| {
+            (value + neighbours.iter().sum::<f32>()) / (neighbours.len() + 1) as f32
+        });
+    }
+
+    /// Increases the contrast of the mask by pushing every vertex away from the average of its neighbours.
+    #[instrument(skip_all)]
+    pub fn sharpen_mask(&mut self, mesh_graph: &MeshGraph) {
+        self.filter_mask(mesh_graph, |value, neighbours| {
+            if neighbours.is_empty() {
+                return value;
+            }
+
+            let average = neighbours.iter().sum::<f32>() / neighbours.len() as f32;
+            value + (value - average)
+        });
+    }
+
+    /// Computes the new mask value of every vertex from its current value and the values of its neighbours.
+    fn filter_mask(&mut self, mesh_graph: &MeshGraph, filter: impl Fn(f32, &[f32]) -> f32) {
+        let mut neighbours = Vec::new();
+
+        let mask = mesh_graph
+            .vertices
+            .iter()
+            .filter_map(|(vertex_id, vertex)| {
+                neighbours.clear();
+                neighbours.extend(vertex.neighbours(mesh_graph).map(|n| self.mask(n)));
+
+                let value = filter(self.mask(vertex_id), &neighbours).clamp(0.0, 1.0);
+                (value > 0.0).then_some((vertex_id, value))
+            })
+            .collect();
+
+        self.mask = mask;
+    }
+}
@@ -0,0 +1,283 @@
+use std::f32::consts::{PI, TAU};
+
+use glam::Vec3;
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
+use tracing::instrument;
+
+use crate::utils::{is_boundary_vertex, vertex_normal};
+
+/// The discrete curvature of the surface around a vertex.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VertexCurvature {
+    /// Mean curvature from the cotangent Laplacian. Positive on convex parts like the outside of a sphere
+    /// (`1 / radius`) and negative on concave parts.
+    pub mean: f32,
+
+    /// Gaussian curvature from the angle defect. Positive on elliptic parts like spheres,
+    /// negative on saddles and zero on flat or cylindrical parts.
+    pub gaussian: f32,
+
+    /// How deep the vertex lies in a cavity relative to its neighbours. See [`cavity`].
+    pub cavity: f32,
+}
+
+impl VertexCurvature {
+    /// Computes the curvature of the given vertex.
+    ///
+    /// Returns `None` if the vertex doesn't exist or has no incident faces with an area.
+    pub fn compute(mesh_graph: &MeshGraph, vertex_id: VertexId) -> Option<Self> {
+        let pos = *mesh_graph
+            .positions
+            .get(vertex_id)
+            .or_else(error_none!("Vertex position not found"))?;
+        let vertex = mesh_graph
+            .vertices
+            .get(vertex_id)
+            .or_else(error_none!("Vertex not found"))?;
+
+        let mut laplacian = Vec3::ZERO;
+        let mut angle_sum = 0.0;
+        let mut area = 0.0;
+
+        for face_id in vertex.faces(mesh_graph) {
+            let Some(face) = mesh_graph
+                .faces
+                .get(face_id)
+                .or_else(error_none!("Face not found"))
+            else {
+                continue;
+            };
+
+            let vertex_ids = face.vertices(mesh_graph).collect::<Vec<_>>();
+            let Some(index) = vertex_ids.iter().position(|&id| id == vertex_id) else {
+                continue;
+            };
+
+            let (Some(&pos_j), Some(&pos_k)) = (
+                mesh_graph.positions.get(vertex_ids[(index + 1) % 3]),
+                mesh_graph.positions.get(vertex_ids[(index + 2) % 3]),
+            ) else {
+                continue;
+            };
+
+            let face_area = (pos_j - pos).cross(pos_k - pos).length() * 0.5;
+            if face_area <= f32::EPSILON {
+                continue;
+            }
+
+            // the angle at k is opposite to the edge to j and vice versa
+            laplacian += cotangent(pos - pos_k, pos_j - pos_k) * (pos_j - pos)
+                + cotangent(pos - pos_j, pos_k - pos_j) * (pos_k - pos);
+            angle_sum += (pos_j - pos).angle_between(pos_k - pos);
+            area += face_area / 3.0;
+        }
+
+        if area <= f32::EPSILON {
+            return None;
+        }
+
+        let normal = vertex_normal(vertex_id, mesh_graph).unwrap_or_default();
+
+        let full_angle = if is_boundary_vertex(vertex_id, mesh_graph) {
+            PI
+        } else {
+            TAU
+        };
+
+        Some(Self {
+            mean: -laplacian.dot(normal) / (4.0 * area),
+            gaussian: (full_angle - angle_sum) / area,
+            cavity: cavity(mesh_graph, vertex_id).unwrap_or_default(),
+        })
+    }
+}
+
+/// How deep the vertex lies in a cavity relative to its neighbours.
+///
+/// Positive in cavities and negative on peaks. It is the offset of the vertex from the average of its neighbours
+/// along the vertex normal divided by the average edge length, so it doesn't depend on the resolution of the mesh.
+pub fn cavity(mesh_graph: &MeshGraph, vertex_id: VertexId) -> Option<f32> {
+    let pos = *mesh_graph.positions.get(vertex_id)?;
+    let normal = vertex_normal(vertex_id, mesh_graph)?;
+
+    let neighbours = mesh_graph
+        .vertices
+        .get(vertex_id)?
+        .neighbours(mesh_graph)
+        .filter_map(|neighbour| mesh_graph.positions.get(neighbour).copied())
+        .collect::<Vec<_>>();
+
+    if neighbours.is_empty() {
+        return None;
+    }
+
+    let count = neighbours.len() as f32;
+    let center = neighbours.iter().sum::<Vec3>() / count;
+    let edge_length = neighbours.iter().map(|n| n.distance(pos)).sum::<f32>() / count;
+
+    if edge_length <= f32::EPSILON {
+        return None;
+    }
+
+    Some((center - pos).dot(normal) / edge_length)
+}
+
+/// The curvature of all vertices of a mesh graph.
+///
+/// Computing it for the whole mesh is expensive, so after a stroke only the touched region
+/// should be recomputed with [`CurvatureMap::update_selection`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CurvatureMap {
+    /// The curvature per vertex. Vertices whose curvature couldn't be computed don't have an entry.
+    pub curvatures: HashMap<VertexId, VertexCurvature>,
+}
+
+impl CurvatureMap {
+    /// Computes the curvature of all vertices of the mesh graph.
+    #[instrument(skip(mesh_graph))]
+    pub fn compute(mesh_graph: &MeshGraph) -> Self {
+        let mut curvature_map = Self::default();
+        curvature_map.update_vertices(mesh_graph, mesh_graph.vertices.keys());
+        curvature_map
+    }
+
+    /// Returns the curvature of the given vertex.
+    #[inline]
+    pub fn get(&self, vertex_id: VertexId) -> Option<VertexCurvature> {
+        self.curvatures.get(&vertex_id).copied()
+    }
+
+    /// Recomputes the curvature of the given vertices.
+    ///
+    /// The curvature depends on the positions of the neighbours, so after moving vertices
+    /// their neighbours have to be updated as well. [`CurvatureMap::update_selection`] does that.
+    pub fn update_vertices(
+        &mut self,
+        mesh_graph: &MeshGraph,
+        vertex_ids: impl IntoIterator<Item = VertexId>,
+    ) {
+        for vertex_id in vertex_ids {
+            match VertexCurvature::compute(mesh_graph, vertex_id) {
+                Some(curvature) => {
+                    self.curvatures.insert(vertex_id, curvature);
+                }
+                None => {
+                    self.curvatures.remove(&vertex_id);
+                }
+            }
+        }
+    }
+
+    /// Recomputes the curvature of the vertices of the selection and their neighbours, and
+    /// removes the vertices that don't exist anymore.
+    ///
+    /// Call this after [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes)
+    /// with the [`DeformationField::selection`](crate::deformation::DeformationField::selection), which
+    /// contains the faces created by the dynamic topology.
+    #[instrument(skip(self, mesh_graph, selection))]
+    pub fn update_selection(&mut self, mesh_graph: &MeshGraph, selection: &Selection) {
+        self.curvatures
+            .retain(|&vertex_id, _| mesh_graph.vertices.contains_key(vertex_id));
+
+        let mut vertex_ids = HashSet::new();
+
+        for vertex_id in selection.resolve_to_vertices(mesh_graph) {
+            if let Some(vertex) = mesh_graph.vertices.get(vertex_id) {
+                vertex_ids.insert(vertex_id);
+                vertex_ids.extend(vertex.neighbours(mesh_graph));
+            }
+        }
+
+        self.update_vertices(mesh_graph, vertex_ids);
+    }
+}
+
+/// Cotangent of the angle between `a` and `b`.
+#[inline]
+fn cotangent(a: Vec3, b: Vec3) -> f32 {
+    let sin = a.cross(b).length();
+
+    if sin <= f32::EPSILON {
+        0.0
+    } else {
+        a.dot(b) / sin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mesh_graph::primitives::IcoSphere;
+
+    use super::*;
+
+    fn sphere(radius: f32) -> MeshGraph {
+        MeshGraph::from(IcoSphere {
+            radius,
+            subdivisions: 3,
+        })
+    }
+
+    /// Checks every vertex against `expected` with a loose tolerance, because the vertices
+    /// with five neighbours deviate the most, and the average with a tight one.
+    fn assert_close_to(values: &[f32], expected: f32) {
+        for value in values {
+            assert!(
+                (value / expected - 1.0).abs() < 0.2,
+                "{value} is not close to {expected}"
+            );
+        }
+
+        let average = values.iter().sum::<f32>() / values.len() as f32;
+        assert!(
+            (average / expected - 1.0).abs() < 0.05,
+            "average {average} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn sphere_mean_curvature_is_inverse_radius() {
+        for radius in [0.5, 1.0, 3.0] {
+            let curvature_map = CurvatureMap::compute(&sphere(radius));
+
+            let mean = curvature_map
+                .curvatures
+                .values()
+                .map(|curvature| curvature.mean)
+                .collect::<Vec<_>>();
+
+            assert_close_to(&mean, 1.0 / radius);
+        }
+    }
+
+    #[test]
+    fn sphere_gaussian_curvature_is_inverse_radius_squared() {
+        for radius in [0.5, 1.0, 3.0] {
+            let curvature_map = CurvatureMap::compute(&sphere(radius));
+
+            let gaussian = curvature_map
+                .curvatures
+                .values()
+                .map(|curvature| curvature.gaussian)
+                .collect::<Vec<_>>();
+
+            assert_close_to(&gaussian, 1.0 / (radius * radius));
+        }
+    }
+
+    #[test]
+    fn sphere_has_no_cavities() {
+        let mesh_graph = sphere(1.0);
+        let curvature_map = CurvatureMap::compute(&mesh_graph);
+
+        assert_eq!(curvature_map.curvatures.len(), mesh_graph.vertices.len());
+        assert!(
+            curvature_map
+                .curvatures
+                .values()
+                .all(|curvature| curvature.cavity < 0.0)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use glam::Vec3;
+use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
+use tracing::{error, instrument};
+
+use crate::{
+    SculptParams,
+    attributes::MeshAttributes,
+    ray::FaceIntersection,
+    selectors::{MeshSelector, SelectionInput, SelectionWeights, WeightedSelection},
+};
+
+use super::{ApplyReport, DeformationField};
+
+/// Mask brush.
+///
+/// This doesn't move any vertices but paints the [`MeshAttributes::mask`] with the weights of the selection.
+/// A positive strength adds to the mask and a negative strength removes from it.
+///
+/// The mask is part of the attributes, so this has to be applied with [`DeformationField::apply_with_attributes`].
+/// The dynamic topology is not run while painting.
+#[derive(Default)]
+pub struct MaskDeformation {
+    selection: Selection,
+    weights: SelectionWeights,
+}
+
+impl DeformationField for MaskDeformation {
+    fn on_pointer_down(
+        &mut self,
+        mesh_graph: &MeshGraph,
+        selector: &dyn MeshSelector,
+        face_intersection: FaceIntersection,
+        attributes: Option<&MeshAttributes>,
+    ) {
+        WeightedSelection {
+            selection: self.selection,
+            weights: self.weights,
+        } = selector.select(
+            mesh_graph,
+            &SelectionInput {
+                attributes,
+                ..face_intersection.into()
+            },
+        );
+    }
+
+    fn on_pointer_move(
+        &mut self,
+        mesh_graph: &MeshGraph,
+        selector: &dyn MeshSelector,
+        _pointer_translation: Vec3,
+        face_intersection: Option<FaceIntersection>,
+        attributes: Option<&MeshAttributes>,
+    ) -> bool {
+        if let Some(face_intersection) = face_intersection {
+            WeightedSelection {
+                selection: self.selection,
+                weights: self.weights,
+            } = selector.select(
+                mesh_graph,
+                &SelectionInput {
+                    attributes,
+                    ..face_intersection.into()
+                },
+            );
+
+            true
+        } else {
+            false
+        }
+    }
+
+    #[inline(always)]
+    fn vertex_movement(&self, _vertex: VertexId, _mesh_graph: &MeshGraph) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    /// The mask is part of the attributes, so this only logs an error. Use
+    /// [`DeformationField::apply_with_attributes`] instead.
+    fn apply(
+        &mut self,
+        _mesh_graph: &mut MeshGraph,
+        _strength: f32,
+        _params: SculptParams,
+    ) -> ApplyReport {
+        error!(
+            "MaskDeformation paints the mask of the attributes. Use apply_with_attributes instead"
+        );
+        ApplyReport::default()
+    }
+
+    #[instrument(skip(self, mesh_graph, attributes))]
+    fn apply_with_attributes(
+        &mut self,
+        mesh_graph: &mut MeshGraph,
+        attributes: &mut MeshAttributes,
+        strength: f32,
+        _params: SculptParams,
+    ) -> ApplyReport {
+        for vertex_id in self.selection.resolve_to_vertices(mesh_graph) {
+            let Some(pos) = mesh_graph
+                .positions
+                .get(vertex_id)
+                .or_else(error_none!("Vertex position not found"))
+            else {
+                continue;
+            };
+
+            let weight = self.weights.weight(vertex_id, *pos);
+
+            attributes.set_mask(vertex_id, attributes.mask(vertex_id) + weight * strength);
+        }
+
+        ApplyReport::default()
+    }
+
+    #[inline(always)]
+    fn selection(&self) -> &Selection {
+        &self.selection
+    }
+
+    #[inline(always)]
+    fn selection_mut(&mut self) -> &mut Selection {
+        &mut self.selection
+    }
+
+    #[inline(always)]
+    fn weights(&self) -> &SelectionWeights {
+        &self.weights
+    }
+
+    #[inline(always)]
+    fn weights_mut(&mut self) -> &mut SelectionWeights {
+        &mut self.weights
+    }
+}
@@ -1,7 +1,15 @@
+mod mask;
+mod self_collision;
 mod smooth;
+mod topology;
 mod traits;
 mod translate;
+mod volume;
 
+pub use mask::*;
+pub use self_collision::*;
 pub use smooth::*;
+pub(crate) use topology::*;
 pub use traits::*;
 pub use translate::*;
+pub use volume::*;
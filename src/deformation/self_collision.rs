@@ -0,0 +1,137 @@
+use glam::Vec3;
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{FaceId, MeshGraph, VertexId};
+use parry3d::{math::Isometry, query::intersection_test};
+use tracing::{error, instrument};
+
+/// How often the movement of intersecting faces is reverted before giving up.
+const MAX_CLAMP_ROUNDS: usize = 4;
+
+/// Defines what [`DeformationField::apply_with_attributes`](super::DeformationField::apply_with_attributes) does when the
+/// deformed part of the mesh penetrates other parts of the mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfCollisionMode {
+    /// Self-intersections are not checked.
+    #[default]
+    Off,
+
+    /// Vertices of faces that would penetrate other faces are not moved.
+    Clamp,
+
+    /// Vertices are moved regardless, but the penetrating faces are reported in the
+    /// [`ApplyReport`](super::ApplyReport).
+    Report,
+}
+
+/// Finds the faces among `face_ids` that intersect any other face of the mesh graph
+/// that they don't share a vertex with.
+///
+/// The BVH entries of `face_ids` are updated first because their vertices have been moved.
+#[instrument(skip_all)]
+pub(crate) fn find_self_intersections(
+    mesh_graph: &mut MeshGraph,
+    face_ids: &HashSet<FaceId>,
+) -> Vec<FaceId> {
+    for &face_id in face_ids {
+        if let Some(face) = mesh_graph.faces.get(face_id) {
+            mesh_graph
+                .bvh
+                .insert_or_update_partially(face.aabb(mesh_graph), face.index, 0.0);
+        }
+    }
+    mesh_graph.refit_bvh();
+
+    let mut intersecting = Vec::new();
+
+    for &face_id in face_ids {
+        let Some(face) = mesh_graph.faces.get(face_id) else {
+            continue;
+        };
+
+        let vertices = face.vertices(mesh_graph).collect::<Vec<_>>();
+        let triangle = mesh_graph.triangle(face.index);
+        let aabb = face.aabb(mesh_graph);
+
+        let intersects = mesh_graph.bvh.intersect_aabb(&aabb).any(|index| {
+            let Some(other) = mesh_graph
+                .index_to_face_id
+                .get(&index)
+                .and_then(|other_id| mesh_graph.faces.get(*other_id))
+            else {
+                return false;
+            };
+
+            // neighbouring faces always touch
+            if other.id == face_id || other.vertices(mesh_graph).any(|v| vertices.contains(&v)) {
+                return false;
+            }
+
+            let other_triangle = mesh_graph.triangle(other.index);
+
+            match intersection_test(
+                &Isometry::identity(),
+                &triangle,
+                &Isometry::identity(),
+                &other_triangle,
+            ) {
+                Ok(intersects) => intersects,
+                Err(_) => {
+                    error!("Triangle intersection test unsupported");
+                    false
+                }
+            }
+        });
+
+        if intersects {
+            intersecting.push(face_id);
+        }
+    }
+
+    intersecting
+}
+
+/// Moves the vertices of penetrating faces back to their positions before the movement
+/// until no face of `face_ids` intersects the rest of the mesh anymore.
+///
+/// Returns the faces that still intersect after giving up.
+#[instrument(skip_all)]
+pub(crate) fn clamp_self_intersections(
+    mesh_graph: &mut MeshGraph,
+    face_ids: &HashSet<FaceId>,
+    old_positions: &HashMap<VertexId, Vec3>,
+) -> Vec<FaceId> {
+    let mut intersecting = find_self_intersections(mesh_graph, face_ids);
+
+    for _ in 0..MAX_CLAMP_ROUNDS {
+        if intersecting.is_empty() {
+            break;
+        }
+
+        let mut reverted = false;
+
+        for &face_id in &intersecting {
+            let Some(face) = mesh_graph.faces.get(face_id) else {
+                continue;
+            };
+
+            for vertex_id in face.vertices(mesh_graph).collect::<Vec<_>>() {
+                if let Some(&old_pos) = old_positions.get(&vertex_id)
+                    && let Some(pos) = mesh_graph.positions.get_mut(vertex_id)
+                    && *pos != old_pos
+                {
+                    *pos = old_pos;
+                    reverted = true;
+                }
+            }
+        }
+
+        if !reverted {
+            break;
+        }
+
+        intersecting = find_self_intersections(mesh_graph, face_ids);
+    }
+
+    intersecting
+}
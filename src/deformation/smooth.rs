@@ -3,8 +3,9 @@ use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
 use tracing::instrument;
 
 use crate::{
+    attributes::MeshAttributes,
     ray::FaceIntersection,
-    selectors::{MeshSelector, WeightedSelection},
+    selectors::{MeshSelector, SelectionInput, SelectionWeights, WeightedSelection},
 };
 
 use super::DeformationField;
@@ -13,18 +14,10 @@ use super::DeformationField;
 ///
 /// This deformation field applies a smoothing effect to the selected vertices.
 /// It calculates the average position of the surrounding vertices of every selected vertex and moves it towards this average.
+#[derive(Default)]
 pub struct SmoothDeformation {
     selection: Selection,
-    weight_callback: Box<dyn Fn(Vec3) -> f32>,
-}
-
-impl Default for SmoothDeformation {
-    fn default() -> Self {
-        Self {
-            selection: Selection::default(),
-            weight_callback: Box::new(|_| 1.0),
-        }
-    }
+    weights: SelectionWeights,
 }
 
 impl DeformationField for SmoothDeformation {
@@ -34,12 +27,19 @@ impl DeformationField for SmoothDeformation {
         selector: &dyn MeshSelector,
         _mouse_translation: Vec3,
         face_intersection: Option<FaceIntersection>,
+        attributes: Option<&MeshAttributes>,
     ) -> bool {
-        if let Some(FaceIntersection { point, face }) = face_intersection {
+        if let Some(face_intersection) = face_intersection {
             WeightedSelection {
                 selection: self.selection,
-                get_weight: self.weight_callback,
-            } = selector.select(mesh_graph, point, face);
+                weights: self.weights,
+            } = selector.select(
+                mesh_graph,
+                &SelectionInput {
+                    attributes,
+                    ..face_intersection.into()
+                },
+            );
 
             true
         } else {
@@ -87,7 +87,12 @@ impl DeformationField for SmoothDeformation {
     }
 
     #[inline(always)]
-    fn weight_callback(&self) -> &dyn Fn(Vec3) -> f32 {
-        self.weight_callback.as_ref()
+    fn weights(&self) -> &SelectionWeights {
+        &self.weights
+    }
+
+    #[inline(always)]
+    fn weights_mut(&mut self) -> &mut SelectionWeights {
+        &mut self.weights
     }
 }
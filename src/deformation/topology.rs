@@ -0,0 +1,590 @@
+use glam::Vec3;
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{Halfedge, HalfedgeId, MeshGraph, Selection, SelectionOps, VertexId, error_none};
+use tracing::{error, instrument};
+
+use crate::{
+    SculptParams, attributes::MeshAttributes, selectors::SelectionWeights, utils::face_area_normal,
+};
+
+/// Collapses and subdivides the edges of the selection as configured by [`SculptParams::topology_mode`].
+///
+/// This does the same as the operations of the mesh graph but additionally keeps `attributes` up to date
+/// and interpolates per-vertex weights. If there is nothing to keep up to date, the operations of the
+/// mesh graph are used directly.
+pub(crate) fn update_topology(
+    mesh_graph: &mut MeshGraph,
+    selection: &mut Selection,
+    attributes: &mut MeshAttributes,
+    weights: &mut SelectionWeights,
+    params: SculptParams,
+) {
+    if params.feature_angle.is_none()
+        && attributes.is_empty()
+        && matches!(weights, SelectionWeights::Callback(_))
+    {
+        if params.topology_mode.collapses() {
+            mesh_graph
+                .collapse_until_edges_above_min_length(params.min_edge_length_squared, selection);
+        }
+
+        if params.topology_mode.subdivides() {
+            mesh_graph
+                .subdivide_until_edges_below_max_length(params.max_edge_length_squared, selection);
+        }
+
+        return;
+    }
+
+    if params.topology_mode.collapses() {
+        collapse_short_edges(
+            mesh_graph,
+            params.min_edge_length_squared,
+            params.feature_angle,
+            selection,
+            attributes,
+            weights,
+        );
+    }
+
+    if params.topology_mode.subdivides() {
+        subdivide_long_edges(
+            mesh_graph,
+            params.max_edge_length_squared,
+            selection,
+            attributes,
+            weights,
+        );
+    }
+}
+
+/// How an edge may be collapsed without destroying feature edges.
+enum FeatureCollapse {
+    /// The edge can be collapsed into its center.
+    Center(HalfedgeId),
+    /// The edge can be collapsed into the given position on the feature. The neighbouring vertices
+    /// must not be moved by the collapse.
+    Pinned(HalfedgeId, Vec3),
+    /// The edge must not be collapsed.
+    Forbidden,
+}
+
+/// Same as `MeshGraph::collapse_until_edges_above_min_length` but edges are never collapsed across feature edges
+/// and the weights of the collapsed vertices are merged.
+///
+/// - Edges between a feature vertex and a regular vertex are collapsed into the feature vertex.
+/// - Edges between two feature vertices are only collapsed if they're part of the same crease.
+///
+/// If `feature_angle` is given, sharp edges around every edge are detected right before it's collapsed.
+/// This way the collapses never have to look at more of the mesh than the edges that are too short.
+#[instrument(skip(mesh_graph, selection, attributes, weights))]
+fn collapse_short_edges(
+    mesh_graph: &mut MeshGraph,
+    min_length_squared: f32,
+    feature_angle: Option<f32>,
+    selection: &mut Selection,
+    attributes: &mut MeshAttributes,
+    weights: &mut SelectionWeights,
+) {
+    let mut halfedges_to_collapse = dedup_twins(mesh_graph, selection)
+        .into_iter()
+        .filter_map(|he| {
+            let len = mesh_graph.halfedges[he].length_squared(mesh_graph);
+            (len < min_length_squared).then_some((he, len))
+        })
+        .collect::<HashMap<_, _>>();
+
+    while !halfedges_to_collapse.is_empty() {
+        let mut min_len = f32::MAX;
+        let mut min_he = HalfedgeId::default();
+
+        for (&he, &len) in &halfedges_to_collapse {
+            if len < min_len {
+                min_len = len;
+                min_he = he;
+            }
+        }
+
+        halfedges_to_collapse.remove(&min_he);
+
+        if let Some(feature_angle) = feature_angle {
+            detect_features_around_edge(mesh_graph, attributes, feature_angle, min_he);
+        }
+
+        let (collapse_he, pinned_position) = match feature_collapse(mesh_graph, attributes, min_he)
+        {
+            FeatureCollapse::Center(he) => (he, None),
+            FeatureCollapse::Pinned(he, position) => (he, Some(position)),
+            FeatureCollapse::Forbidden => continue,
+        };
+
+        let start_vertex = mesh_graph.halfedges[collapse_he].start_vertex(mesh_graph);
+        let end_vertex = mesh_graph.halfedges[collapse_he].end_vertex;
+
+        // where the merged vertex ends up between the start (0.0) and the end (1.0)
+        let merge_factor = pinned_position
+            .zip(start_vertex)
+            .map(|(pinned_position, start_vertex)| {
+                let start_pos = mesh_graph.positions[start_vertex];
+                let edge = mesh_graph.positions[end_vertex] - start_pos;
+                ((pinned_position - start_pos).dot(edge) / edge.length_squared()).clamp(0.0, 1.0)
+            })
+            .unwrap_or(0.5);
+
+        // The collapse smooths out neighbours of degenerate faces which would move them off their features.
+        // For pinned collapses all neighbours are kept in place. It was checked above that this doesn't flip any faces.
+        let pinned_neighbours = neighbour_positions(
+            mesh_graph,
+            attributes,
+            mesh_graph.halfedges[collapse_he],
+            pinned_position.is_none(),
+        );
+
+        let (verts, halfedges, faces) = mesh_graph.collapse_edge(collapse_he);
+
+        for (vertex_id, position) in pinned_neighbours {
+            if let Some(pos) = mesh_graph.positions.get_mut(vertex_id) {
+                *pos = position;
+            }
+        }
+
+        if let Some(start_vertex) = start_vertex {
+            if let Some(weights) = weights.per_vertex_mut() {
+                interpolate_vertex_value(
+                    weights,
+                    start_vertex,
+                    start_vertex,
+                    end_vertex,
+                    merge_factor,
+                );
+            }
+
+            // masked areas have to stay protected, so the larger mask value survives
+            let end_mask = attributes.mask(end_vertex);
+            if end_mask > attributes.mask(start_vertex) {
+                attributes.set_mask(start_vertex, end_mask);
+            }
+        }
+
+        for vert in verts {
+            selection.remove(vert);
+            attributes.mask.remove(&vert);
+
+            if let Some(weights) = weights.per_vertex_mut() {
+                weights.remove(&vert);
+            }
+        }
+        for halfedge in halfedges {
+            selection.remove(halfedge);
+            halfedges_to_collapse.remove(&halfedge);
+            attributes.feature_edges.remove(&halfedge);
+        }
+        for face in faces {
+            selection.remove(face);
+            attributes.face_sets.remove(&face);
+        }
+
+        let Some(start_vertex) = start_vertex else {
+            error!("Start vertex not found");
+            continue;
+        };
+
+        if let Some(pinned_position) = pinned_position
+            && let Some(pos) = mesh_graph.positions.get_mut(start_vertex)
+        {
+            *pos = pinned_position;
+        }
+
+        attributes.sync_feature_twins(mesh_graph, start_vertex);
+
+        let outgoing_halfedges = mesh_graph
+            .vertices
+            .get(start_vertex)
+            .or_else(error_none!("Vertex for start vertex not found"))
+            .map(|vertex| vertex.outgoing_halfedges(mesh_graph).collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for halfedge_id in outgoing_halfedges {
+            let Some(halfedge) = mesh_graph
+                .halfedges
+                .get(halfedge_id)
+                .or_else(error_none!("Halfedge not found"))
+            else {
+                continue;
+            };
+
+            let len = halfedge.length_squared(mesh_graph);
+
+            if len < min_length_squared {
+                halfedges_to_collapse.insert(halfedge_id, len);
+            } else {
+                halfedges_to_collapse.remove(&halfedge_id);
+            }
+
+            if let Some(twin) = halfedge.twin {
+                halfedges_to_collapse.remove(&twin);
+            }
+
+            if let Some(face_id) = halfedge.face {
+                if let Some(face) = mesh_graph.faces.get(face_id) {
+                    mesh_graph.bvh.insert_or_update_partially(
+                        face.aabb(mesh_graph),
+                        face.index,
+                        0.0,
+                    );
+                } else {
+                    error!("Face not found. BVH will not be updated.");
+                }
+            }
+
+            selection.insert(halfedge_id);
+        }
+    }
+}
+
+/// Detects the feature edges of the vertices that are affected by collapsing the edge of `halfedge_id`.
+///
+/// These are the two vertices of the edge and their neighbours which might be moved by the collapse.
+fn detect_features_around_edge(
+    mesh_graph: &MeshGraph,
+    attributes: &mut MeshAttributes,
+    feature_angle: f32,
+    halfedge_id: HalfedgeId,
+) {
+    let Some(he) = mesh_graph.halfedges.get(halfedge_id) else {
+        return;
+    };
+
+    let vertex_ids = [he.start_vertex(mesh_graph), Some(he.end_vertex)]
+        .into_iter()
+        .flatten()
+        .filter_map(|vertex_id| mesh_graph.vertices.get(vertex_id))
+        .flat_map(|vertex| vertex.neighbours(mesh_graph))
+        .collect::<HashSet<_>>();
+
+    attributes.detect_feature_edges_around_vertices(mesh_graph, feature_angle, vertex_ids);
+}
+
+/// Positions of the neighbours of the start and end vertex of `halfedge`.
+///
+/// If `only_features` is `true`, only the neighbours that are part of a feature edge are returned.
+fn neighbour_positions(
+    mesh_graph: &MeshGraph,
+    attributes: &MeshAttributes,
+    halfedge: Halfedge,
+    only_features: bool,
+) -> Vec<(VertexId, Vec3)> {
+    [halfedge.start_vertex(mesh_graph), Some(halfedge.end_vertex)]
+        .into_iter()
+        .flatten()
+        .filter_map(|vertex_id| mesh_graph.vertices.get(vertex_id))
+        .flat_map(|vertex| vertex.neighbours(mesh_graph))
+        .filter(|&vertex_id| {
+            !only_features || attributes.feature_edge_count(mesh_graph, vertex_id) > 0
+        })
+        .filter_map(|vertex_id| Some((vertex_id, *mesh_graph.positions.get(vertex_id)?)))
+        .collect()
+}
+
+/// Decides how the edge of `halfedge_id` can be collapsed with respect to the feature edges.
+fn feature_collapse(
+    mesh_graph: &MeshGraph,
+    attributes: &MeshAttributes,
+    halfedge_id: HalfedgeId,
+) -> FeatureCollapse {
+    let Some(he) = mesh_graph.halfedges.get(halfedge_id) else {
+        return FeatureCollapse::Forbidden;
+    };
+    let Some(twin_id) = he.twin else {
+        return FeatureCollapse::Forbidden;
+    };
+    let Some(start_vertex) = he.start_vertex(mesh_graph) else {
+        return FeatureCollapse::Forbidden;
+    };
+    let end_vertex = he.end_vertex;
+
+    let start_count = attributes.feature_edge_count(mesh_graph, start_vertex);
+    let end_count = attributes.feature_edge_count(mesh_graph, end_vertex);
+
+    if attributes.is_feature_edge(halfedge_id) {
+        // only shorten creases but never remove corners or adjacent creases
+        let other_edges_regular = [halfedge_id, twin_id].into_iter().all(|he_id| {
+            let Some(face_id) = mesh_graph.halfedges[he_id].face else {
+                return true;
+            };
+            mesh_graph.faces.get(face_id).is_none_or(|face| {
+                face.halfedges(mesh_graph)
+                    .all(|face_he| face_he == he_id || !attributes.is_feature_edge(face_he))
+            })
+        });
+
+        let start_pos = mesh_graph.positions[start_vertex];
+        let center = (start_pos + mesh_graph.positions[end_vertex]) * 0.5;
+
+        return if start_count == 2
+            && end_count == 2
+            && other_edges_regular
+            && !moving_vertex_flips_faces(mesh_graph, start_vertex, end_vertex, center)
+            && !moving_vertex_flips_faces(mesh_graph, end_vertex, start_vertex, center)
+        {
+            FeatureCollapse::Pinned(halfedge_id, center)
+        } else {
+            FeatureCollapse::Forbidden
+        };
+    }
+
+    match (start_count > 0, end_count > 0) {
+        (false, false) => FeatureCollapse::Center(halfedge_id),
+        (true, false)
+            if !moving_vertex_flips_faces(
+                mesh_graph,
+                end_vertex,
+                start_vertex,
+                mesh_graph.positions[start_vertex],
+            ) =>
+        {
+            FeatureCollapse::Pinned(halfedge_id, mesh_graph.positions[start_vertex])
+        }
+        (false, true)
+            if !moving_vertex_flips_faces(
+                mesh_graph,
+                start_vertex,
+                end_vertex,
+                mesh_graph.positions[end_vertex],
+            ) =>
+        {
+            FeatureCollapse::Pinned(twin_id, mesh_graph.positions[end_vertex])
+        }
+        _ => FeatureCollapse::Forbidden,
+    }
+}
+
+/// Checks if moving `vertex_id` to `target` would flip any of its faces that don't contain `other_vertex_id`.
+fn moving_vertex_flips_faces(
+    mesh_graph: &MeshGraph,
+    vertex_id: VertexId,
+    other_vertex_id: VertexId,
+    target: Vec3,
+) -> bool {
+    let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+        return true;
+    };
+
+    vertex.faces(mesh_graph).any(|face_id| {
+        let Some(face) = mesh_graph.faces.get(face_id) else {
+            return false;
+        };
+
+        let vertices = face.vertices(mesh_graph).collect::<Vec<_>>();
+        if vertices.contains(&other_vertex_id) {
+            return false;
+        }
+
+        let Some(normal_before) = face_area_normal(face_id, mesh_graph) else {
+            return false;
+        };
+
+        let positions = vertices
+            .iter()
+            .map(|&v_id| {
+                if v_id == vertex_id {
+                    target
+                } else {
+                    mesh_graph.positions[v_id]
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let [a, b, c] = positions[..] else {
+            return false;
+        };
+
+        normal_before.dot((b - a).cross(c - a)) <= 0.0
+    })
+}
+
+/// Same as `MeshGraph::subdivide_until_edges_below_max_length` but both halves of a split
+/// feature edge stay feature edges and the weights of the new vertices are interpolated.
+#[instrument(skip(mesh_graph, selection, attributes, weights))]
+fn subdivide_long_edges(
+    mesh_graph: &mut MeshGraph,
+    max_length_squared: f32,
+    selection: &mut Selection,
+    attributes: &mut MeshAttributes,
+    weights: &mut SelectionWeights,
+) {
+    let mut halfedges_to_subdivide = dedup_twins(mesh_graph, selection)
+        .into_iter()
+        .filter_map(|he| {
+            let len = mesh_graph.halfedges[he].length_squared(mesh_graph);
+            (len > max_length_squared).then_some((he, len))
+        })
+        .collect::<HashMap<_, _>>();
+
+    while !halfedges_to_subdivide.is_empty() {
+        let mut max_len = 0.0;
+        let mut max_he_id = HalfedgeId::default();
+
+        for (&he, &len) in &halfedges_to_subdivide {
+            if len > max_len {
+                max_len = len;
+                max_he_id = he;
+            }
+        }
+
+        halfedges_to_subdivide.remove(&max_he_id);
+
+        let mut affected_faces = Selection::default();
+
+        let max_he = mesh_graph.halfedges[max_he_id];
+        for face_id in [
+            max_he.face,
+            max_he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            selection.insert(face_id);
+            affected_faces.insert(face_id);
+        }
+
+        let is_feature = attributes.is_feature_edge(max_he_id);
+        let face_sets = [
+            max_he.face,
+            max_he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face),
+        ]
+        .map(|face_id| face_id.map(|face_id| attributes.face_set(face_id)));
+        // the twin is split as well, so this has to be looked up before
+        let start_vertex = max_he.start_vertex(mesh_graph);
+
+        let new_edges = mesh_graph.subdivide_edge(max_he_id);
+
+        // `max_he_id` now ends at the center and the first new halfedge continues to the original end
+        if is_feature && let Some(&second_half) = new_edges.first() {
+            attributes.tag_feature_edge(mesh_graph, max_he_id);
+            attributes.tag_feature_edge(mesh_graph, second_half);
+        }
+
+        // the first new halfedge is in the face split off from the face of `max_he_id` and
+        // the new twin of `max_he_id` is in the face split off from the face of the original twin
+        let new_faces = [
+            new_edges
+                .first()
+                .and_then(|&he_id| mesh_graph.halfedges.get(he_id))
+                .and_then(|he| he.face),
+            mesh_graph
+                .halfedges
+                .get(max_he_id)
+                .and_then(|he| he.twin)
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face),
+        ];
+
+        for (new_face, face_set) in new_faces.into_iter().zip(face_sets) {
+            if let Some(new_face) = new_face
+                && let Some(face_set) = face_set
+            {
+                attributes.assign_face_set([new_face], face_set);
+            }
+        }
+
+        if let Some(start_vertex) = start_vertex
+            && let Some(center) = mesh_graph.halfedges.get(max_he_id).map(|he| he.end_vertex)
+        {
+            if let Some(weights) = weights.per_vertex_mut() {
+                interpolate_vertex_value(weights, center, start_vertex, max_he.end_vertex, 0.5);
+            }
+
+            interpolate_vertex_value(
+                &mut attributes.mask,
+                center,
+                start_vertex,
+                max_he.end_vertex,
+                0.5,
+            );
+        }
+
+        for new_he_id in new_edges {
+            let new_he = mesh_graph.halfedges[new_he_id];
+
+            let twin_face = new_he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face);
+
+            for face_id in [new_he.face, twin_face].into_iter().flatten() {
+                selection.insert(face_id);
+                affected_faces.insert(face_id);
+            }
+        }
+
+        for he_id in affected_faces.resolve_to_halfedges(mesh_graph) {
+            let Some(he) = mesh_graph
+                .halfedges
+                .get(he_id)
+                .or_else(error_none!("Halfedge not found"))
+            else {
+                continue;
+            };
+
+            if he
+                .twin
+                .is_some_and(|twin| halfedges_to_subdivide.contains_key(&twin))
+            {
+                continue;
+            }
+
+            let len_sqr = he.length_squared(mesh_graph);
+
+            if len_sqr > max_length_squared {
+                halfedges_to_subdivide.insert(he_id, len_sqr);
+            }
+        }
+    }
+}
+
+/// Sets the value of `target` by linearly interpolating between the values of `start` and `end`.
+/// Vertices without a value count as zero.
+fn interpolate_vertex_value(
+    values: &mut HashMap<VertexId, f32>,
+    target: VertexId,
+    start: VertexId,
+    end: VertexId,
+    factor: f32,
+) {
+    let start_value = values.get(&start).copied().unwrap_or_default();
+    let end_value = values.get(&end).copied().unwrap_or_default();
+
+    let value = start_value + (end_value - start_value) * factor;
+
+    if value == 0.0 {
+        values.remove(&target);
+    } else {
+        values.insert(target, value);
+    }
+}
+
+/// Resolves the selection to halfedges and keeps only one halfedge per edge.
+fn dedup_twins(mesh_graph: &MeshGraph, selection: &Selection) -> HashSet<HalfedgeId> {
+    let mut dedup_halfedges = HashSet::new();
+
+    for he in selection.resolve_to_halfedges(mesh_graph) {
+        let twin_already_in = mesh_graph
+            .halfedges
+            .get(he)
+            .and_then(|he| he.twin)
+            .is_some_and(|twin| dedup_halfedges.contains(&twin));
+
+        if !twin_already_in {
+            dedup_halfedges.insert(he);
+        }
+    }
+
+    dedup_halfedges
+}
@@ -1,11 +1,33 @@
+use std::mem;
+
 use glam::Vec3;
-use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{FaceId, MeshGraph, Selection, VertexId, error_none};
 use tracing::{error, instrument};
 
-use crate::{ray::FaceIntersection, selectors::MeshSelector};
+use crate::{
+    attributes::MeshAttributes,
+    ray::FaceIntersection,
+    selectors::{MeshSelector, SelectionWeights, faces_incident_to_vertices},
+};
 
 use crate::SculptParams;
 
+use super::{
+    SelfCollisionMode, clamp_self_intersections, find_self_intersections, preserve_volume,
+    signed_volume_of_faces, update_topology,
+};
+
+/// Information about what happened during [`DeformationField::apply_with_attributes`].
+#[derive(Debug, Clone, Default)]
+pub struct ApplyReport {
+    /// Faces that penetrate other parts of the mesh after the deformation.
+    ///
+    /// This is only filled if [`SculptParams::self_collision`] is not [`SelfCollisionMode::Off`].
+    /// With [`SelfCollisionMode::Clamp`] these are the faces whose intersections couldn't be resolved.
+    pub self_intersections: Vec<FaceId>,
+}
+
 /// Trait for deformation fields.
 ///
 /// It describes how vertices should be moved based on factors like
@@ -16,12 +38,16 @@ pub trait DeformationField {
 
     /// Called when the pointer is pressed.
     ///
-    /// The parameter `face_intersection` is the intersection of the pointer with the mesh.
+    /// Parameters:
+    /// - `face_intersection` is the intersection of the pointer with the mesh.
+    /// - `attributes` are passed to the selector in [`SelectionInput::attributes`](crate::selectors::SelectionInput::attributes).
+    ///   Selectors that use face sets like [`ComponentWithFalloff`](crate::selectors::ComponentWithFalloff) need them.
     fn on_pointer_down(
         &mut self,
         _mesh_graph: &MeshGraph,
         _selector: &dyn MeshSelector,
         _face_intersection: FaceIntersection,
+        _attributes: Option<&MeshAttributes>,
     ) {
         // by default, do nothing
     }
@@ -31,6 +57,7 @@ pub trait DeformationField {
     /// Parameters:
     /// - `pointer_translation` is the translation of the pointer in 3D space.
     /// - `face_intersection` is the intersection of the pointer with the mesh.
+    /// - `attributes` are passed to the selector like in [`DeformationField::on_pointer_down`].
     ///
     /// It returns true if the deformation should be applied after this.
     fn on_pointer_move(
@@ -39,6 +66,7 @@ pub trait DeformationField {
         _selector: &dyn MeshSelector,
         _pointer_translation: Vec3,
         _face_intersection: Option<FaceIntersection>,
+        _attributes: Option<&MeshAttributes>,
     ) -> bool {
         // by default, do nothing
         true
@@ -50,15 +78,18 @@ pub trait DeformationField {
     /// Same as `selection` but mutable.
     fn selection_mut(&mut self) -> &mut Selection;
 
-    /// Return the current weight callback usually updated by the selector given to `on_pointer_down` and `on_pointer_move`.
-    fn weight_callback(&self) -> &dyn Fn(Vec3) -> f32;
+    /// Return the current weights usually updated by the selector given to `on_pointer_down` and `on_pointer_move`.
+    fn weights(&self) -> &SelectionWeights;
+
+    /// Same as `weights` but mutable.
+    fn weights_mut(&mut self) -> &mut SelectionWeights;
 
     /// This computes the maximum vertex movement of all the affected vertices.
     /// Used to determine the number of steps needed to apply the deformation.
     #[instrument(skip(self, mesh_graph))]
     fn max_movement_squared(&self, mesh_graph: &MeshGraph, strength: f32) -> f32 {
         let affected_vertices = self.selection().resolve_to_vertices(mesh_graph);
-        let get_weight = self.weight_callback();
+        let weights = self.weights();
 
         let mut max_movement_squared: f32 = 0.0;
 
@@ -67,8 +98,7 @@ pub trait DeformationField {
                 .positions
                 .get(*vertex)
                 .or_else(error_none!("Vertex position not found"))
-                .copied()
-                .map(get_weight)
+                .map(|pos| weights.weight(*vertex, *pos))
                 .unwrap_or_default();
 
             let movement = self.vertex_movement(*vertex, mesh_graph) * weight * strength;
@@ -78,11 +108,47 @@ pub trait DeformationField {
         max_movement_squared
     }
 
-    /// This is the main method of this trait. It applies the deformation to the mesh graph.
+    /// Applies the deformation without any [`MeshAttributes`].
     ///
-    /// This method should be called after `on_pointer_move` returns `true`.
+    /// The sculpt mask is ignored and detected feature edges are forgotten after every call.
+    /// Use [`DeformationField::apply_with_attributes`] with the attributes that are stored with the mesh instead.
+    #[deprecated(
+        note = "use `apply_with_attributes` so that the sculpt mask and the feature edges are respected"
+    )]
     #[instrument(skip(self, mesh_graph))]
-    fn apply(&mut self, mesh_graph: &mut MeshGraph, strength: f32, params: SculptParams) {
+    fn apply(
+        &mut self,
+        mesh_graph: &mut MeshGraph,
+        strength: f32,
+        params: SculptParams,
+    ) -> ApplyReport {
+        self.apply_with_attributes(mesh_graph, &mut MeshAttributes::default(), strength, params)
+    }
+
+    /// This is the main method of this trait. It applies the deformation to the mesh graph and keeps
+    /// the given attributes up to date while the dynamic topology changes the mesh.
+    ///
+    /// This method should be called after `on_pointer_move` returns `true`.
+    ///
+    /// Depending on [`SculptParams::self_collision`] the movement is checked for self-intersections.
+    /// If [`SculptParams::preserve_volume`] is set, the selection is offset along its normals after every step
+    /// so that the enclosed volume stays the same. This happens after the clamping of
+    /// [`SelfCollisionMode::Clamp`] and doesn't move the vertices that were clamped.
+    ///
+    /// The weights are multiplied by one minus the [`MeshAttributes::mask`].
+    /// Edges are never collapsed across the feature edges of `attributes` and split feature edges
+    /// stay feature edges. If [`SculptParams::feature_angle`] is set, sharp edges are detected and added
+    /// to the feature edges of `attributes` whenever an edge next to them is about to be collapsed.
+    #[instrument(skip(self, mesh_graph, attributes))]
+    fn apply_with_attributes(
+        &mut self,
+        mesh_graph: &mut MeshGraph,
+        attributes: &mut MeshAttributes,
+        strength: f32,
+        params: SculptParams,
+    ) -> ApplyReport {
+        let mut self_intersections = HashSet::new();
+
         let max_movement_squared = self.max_movement_squared(mesh_graph, strength);
 
         let steps = (max_movement_squared / params.max_move_dist_squared)
@@ -91,6 +157,9 @@ pub trait DeformationField {
 
         let factor = 1.0 / steps;
 
+        // taken out so they can be updated together with the selection by the dynamic topology
+        let mut weights = mem::take(self.weights_mut());
+
         let selection = self.selection_mut();
 
         #[cfg(feature = "rerun")]
@@ -98,10 +167,7 @@ pub trait DeformationField {
             mesh_graph.log_rerun();
         }
 
-        mesh_graph.collapse_until_edges_above_min_length(params.min_edge_length_squared, selection);
-
-        mesh_graph
-            .subdivide_until_edges_below_max_length(params.max_edge_length_squared, selection);
+        update_topology(mesh_graph, selection, attributes, &mut weights, params);
 
         let mut movements = Vec::new();
 
@@ -109,45 +175,88 @@ pub trait DeformationField {
             let affected_vertices = self.selection().resolve_to_vertices(mesh_graph);
             movements.clear();
 
-            let get_weight = self.weight_callback();
-
             for vertex in &affected_vertices {
                 let weight = mesh_graph
                     .positions
                     .get(*vertex)
                     .or_else(error_none!("Vertex position not found"))
-                    .copied()
-                    .map(get_weight)
+                    .map(|pos| weights.weight(*vertex, *pos) * (1.0 - attributes.mask(*vertex)))
                     .unwrap_or_default();
 
                 let movement = self.vertex_movement(*vertex, mesh_graph) * weight * strength;
-                movements.push(movement);
+                movements.push((*vertex, movement, weight));
             }
 
             let selection = self.selection_mut();
 
-            for (vertex, movement) in affected_vertices.iter().zip(movements.iter()) {
-                if let Some(pos) = mesh_graph.positions.get_mut(*vertex) {
-                    *pos += *movement * factor;
+            let affected_faces =
+                if params.preserve_volume || params.self_collision != SelfCollisionMode::Off {
+                    faces_incident_to_vertices(affected_vertices.iter().copied(), mesh_graph)
+                } else {
+                    HashSet::new()
+                };
+
+            let volume_before = params
+                .preserve_volume
+                .then(|| signed_volume_of_faces(mesh_graph, affected_faces.iter().copied()));
+
+            let mut old_positions = HashMap::with_capacity(affected_vertices.len());
+
+            for &(vertex, movement, _) in &movements {
+                if let Some(pos) = mesh_graph.positions.get_mut(vertex) {
+                    old_positions.insert(vertex, *pos);
+                    *pos += movement * factor;
                 } else {
                     error!("Position not found");
                 }
             }
 
+            if params.self_collision == SelfCollisionMode::Clamp {
+                self_intersections.extend(clamp_self_intersections(
+                    mesh_graph,
+                    &affected_faces,
+                    &old_positions,
+                ));
+            }
+
+            if let Some(volume_before) = volume_before {
+                // vertices that were moved back by the clamping keep their position
+                let vertex_weights = movements
+                    .iter()
+                    .map(|&(vertex, movement, weight)| {
+                        let clamped = movement != Vec3::ZERO
+                            && mesh_graph.positions.get(vertex) == old_positions.get(&vertex);
+
+                        (vertex, if clamped { 0.0 } else { weight })
+                    })
+                    .collect::<Vec<_>>();
+
+                preserve_volume(mesh_graph, &affected_faces, &vertex_weights, volume_before);
+            }
+
+            if params.self_collision == SelfCollisionMode::Report {
+                self_intersections.extend(find_self_intersections(mesh_graph, &affected_faces));
+            }
+
             #[cfg(feature = "rerun")]
             {
                 mesh_graph.log_rerun();
             }
 
-            mesh_graph
-                .collapse_until_edges_above_min_length(params.min_edge_length_squared, selection);
-
-            mesh_graph
-                .subdivide_until_edges_below_max_length(params.max_edge_length_squared, selection);
+            update_topology(mesh_graph, selection, attributes, &mut weights, params);
 
             // TODO : merging and separation and cleanup
         }
 
+        *self.weights_mut() = weights;
+
         mesh_graph.refit_bvh();
+
+        // faces might have been removed by later collapses
+        self_intersections.retain(|face_id| mesh_graph.faces.contains_key(*face_id));
+
+        ApplyReport {
+            self_intersections: self_intersections.into_iter().collect(),
+        }
     }
 }
@@ -3,8 +3,9 @@ use mesh_graph::{MeshGraph, Selection};
 use parry3d::{math::Point, query::PointQueryWithLocation};
 
 use crate::{
+    attributes::MeshAttributes,
     ray::FaceIntersection,
-    selectors::{MeshSelector, WeightedSelection},
+    selectors::{MeshSelector, SelectionInput, SelectionWeights, WeightedSelection},
 };
 
 use super::DeformationField;
@@ -14,7 +15,7 @@ use super::DeformationField;
 /// This deformation field translates vertices based on the pointer movement.
 pub struct TranslateDeformation {
     selection: Selection,
-    weight_callback: Box<dyn Fn(Vec3) -> f32>,
+    weights: SelectionWeights,
     translation: Vec3,
     point: Vec3,
 }
@@ -23,7 +24,7 @@ impl Default for TranslateDeformation {
     fn default() -> Self {
         Self {
             selection: Selection::default(),
-            weight_callback: Box::new(|_| 1.0),
+            weights: SelectionWeights::default(),
             translation: Vec3::ZERO,
             point: Vec3::ZERO,
         }
@@ -36,11 +37,18 @@ impl DeformationField for TranslateDeformation {
         mesh_graph: &MeshGraph,
         selector: &dyn MeshSelector,
         face_intersection: FaceIntersection,
+        attributes: Option<&MeshAttributes>,
     ) {
         WeightedSelection {
             selection: self.selection,
-            get_weight: self.weight_callback,
-        } = selector.select(mesh_graph, face_intersection.point, face_intersection.face);
+            weights: self.weights,
+        } = selector.select(
+            mesh_graph,
+            &SelectionInput {
+                attributes,
+                ..face_intersection.into()
+            },
+        );
 
         #[cfg(feature = "rerun")]
         mesh_graph.log_selection_rerun("translate/on_pointer_down", &self.selection);
@@ -62,13 +70,14 @@ impl DeformationField for TranslateDeformation {
         selector: &dyn MeshSelector,
         mouse_translation: Vec3,
         face_intersection: Option<FaceIntersection>,
+        attributes: Option<&MeshAttributes>,
     ) -> bool {
         self.translation = mouse_translation;
 
         self.point += mouse_translation;
 
-        let (face, point) = if let Some(face_intersection) = face_intersection {
-            (face_intersection.face, face_intersection.point)
+        let face_intersection = if let Some(face_intersection) = face_intersection {
+            face_intersection
         } else if let Some((_, face)) = mesh_graph
             .project_local_point_and_get_location_with_max_dist(
                 &Point::new(self.point.x, self.point.y, self.point.z),
@@ -76,15 +85,24 @@ impl DeformationField for TranslateDeformation {
                 f32::MAX,
             )
         {
-            (face, self.point)
+            FaceIntersection {
+                point: self.point,
+                face,
+            }
         } else {
             return false;
         };
 
         WeightedSelection {
             selection: self.selection,
-            get_weight: self.weight_callback,
-        } = selector.select(mesh_graph, point, face);
+            weights: self.weights,
+        } = selector.select(
+            mesh_graph,
+            &SelectionInput {
+                attributes,
+                ..face_intersection.into()
+            },
+        );
 
         #[cfg(feature = "rerun")]
         {
@@ -93,7 +111,7 @@ impl DeformationField for TranslateDeformation {
             mesh_graph::RR
                 .log(
                     "translate/on_pointer_move/self_point",
-                    &rerun::Points3D::new([mesh_graph::utils::vec3_array(point)]),
+                    &rerun::Points3D::new([mesh_graph::utils::vec3_array(face_intersection.point)]),
                 )
                 .unwrap();
         }
@@ -126,7 +144,12 @@ impl DeformationField for TranslateDeformation {
     }
 
     #[inline(always)]
-    fn weight_callback(&self) -> &dyn Fn(Vec3) -> f32 {
-        self.weight_callback.as_ref()
+    fn weights(&self) -> &SelectionWeights {
+        &self.weights
+    }
+
+    #[inline(always)]
+    fn weights_mut(&mut self) -> &mut SelectionWeights {
+        &mut self.weights
     }
 }
@@ -0,0 +1,99 @@
+use hashbrown::HashSet;
+use mesh_graph::{FaceId, MeshGraph, VertexId};
+use tracing::instrument;
+
+use crate::utils::{face_area_normal, vertex_normal};
+
+/// Number of correction rounds done by [`preserve_volume`]. The correction is only exact
+/// to first order, so a second round removes most of the remaining error.
+const VOLUME_CORRECTION_ROUNDS: usize = 2;
+
+/// Computes the volume enclosed by the mesh graph.
+///
+/// The mesh should be closed and consistently oriented, otherwise the result is meaningless.
+/// The volume is negative if the faces point inwards.
+#[instrument(skip(mesh_graph))]
+pub fn enclosed_volume(mesh_graph: &MeshGraph) -> f32 {
+    signed_volume_of_faces(mesh_graph, mesh_graph.faces.keys())
+}
+
+/// Sum of the signed volumes of the tetrahedra spanned by the origin and the given faces.
+///
+/// For a closed mesh this is the enclosed volume. For a subset of the faces this changes by exactly
+/// the same amount as the enclosed volume when only vertices of these faces are moved.
+pub(crate) fn signed_volume_of_faces(
+    mesh_graph: &MeshGraph,
+    face_ids: impl IntoIterator<Item = FaceId>,
+) -> f32 {
+    face_ids
+        .into_iter()
+        .filter_map(|face_id| {
+            let face = mesh_graph.faces.get(face_id)?;
+            let mut positions = face
+                .vertices(mesh_graph)
+                .filter_map(|v_id| mesh_graph.positions.get(v_id));
+
+            let a = positions.next()?;
+            let b = positions.next()?;
+            let c = positions.next()?;
+
+            Some(a.dot(b.cross(*c)) / 6.0)
+        })
+        .sum()
+}
+
+/// Moves the given vertices along their normals so that the signed volume of `face_ids` returns to
+/// `target_volume`. Every vertex is moved proportionally to its weight.
+///
+/// All vertices of `face_ids` that are moved have to be in `vertex_weights`.
+#[instrument(skip_all)]
+pub(crate) fn preserve_volume(
+    mesh_graph: &mut MeshGraph,
+    face_ids: &HashSet<FaceId>,
+    vertex_weights: &[(VertexId, f32)],
+    target_volume: f32,
+) {
+    for _ in 0..VOLUME_CORRECTION_ROUNDS {
+        let volume_change =
+            signed_volume_of_faces(mesh_graph, face_ids.iter().copied()) - target_volume;
+
+        // moving a vertex by `d` along its normal changes the volume by about `d` times a third
+        // of the area of its incident faces
+        let mut offsets = Vec::with_capacity(vertex_weights.len());
+        let mut weighted_area = 0.0;
+
+        for &(vertex_id, weight) in vertex_weights {
+            if weight <= 0.0 {
+                continue;
+            }
+
+            let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+                continue;
+            };
+            let Some(normal) = vertex_normal(vertex_id, mesh_graph) else {
+                continue;
+            };
+
+            let area = vertex
+                .faces(mesh_graph)
+                .filter_map(|face_id| face_area_normal(face_id, mesh_graph))
+                .map(|area_normal| area_normal.length() / 6.0)
+                .sum::<f32>();
+
+            weighted_area += weight * area;
+            offsets.push((vertex_id, normal * weight));
+        }
+
+        if weighted_area <= f32::EPSILON {
+            return;
+        }
+
+        let distance = -volume_change / weighted_area;
+
+        for (vertex_id, offset) in offsets {
+            if let Some(pos) = mesh_graph.positions.get_mut(vertex_id) {
+                *pos += offset * distance;
+            }
+        }
+    }
+}
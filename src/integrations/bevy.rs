@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::ray::Ray;
+use crate::{ray::Ray, transform::MeshTransform};
 
 impl From<Ray3d> for Ray {
     fn from(ray: Ray3d) -> Self {
@@ -10,3 +10,21 @@ impl From<Ray3d> for Ray {
         }
     }
 }
+
+impl From<GlobalTransform> for MeshTransform {
+    fn from(transform: GlobalTransform) -> Self {
+        Self::new(transform.affine())
+    }
+}
+
+impl From<&GlobalTransform> for MeshTransform {
+    fn from(transform: &GlobalTransform) -> Self {
+        Self::new(transform.affine())
+    }
+}
+
+impl From<Transform> for MeshTransform {
+    fn from(transform: Transform) -> Self {
+        Self::new(transform.compute_affine())
+    }
+}
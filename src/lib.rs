@@ -22,16 +22,123 @@
 //!
 //! If you want to implement a custom selection strategy, you can create a struct that implements the [`MeshSelector`] trait. Have a look
 //! at the existing selection strategies in the [`selectors`] module for inspiration.
+//!
+//! ## World transforms
+//!
+//! Everything works in the local space of the mesh graph. If the sculpted object is translated, rotated or scaled,
+//! use a [`transform::MeshTransform`] to convert the pointer ray, the pointer movement and the brush sizes
+//! from world space to local space.
 
+use deformation::SelfCollisionMode;
 use mesh_graph::MeshGraph;
+use transform::MeshTransform;
 
+/// Attributes like feature edges that are kept up to date by the dynamic topology
+pub mod attributes;
+/// Per-vertex curvature and cavity of mesh graphs
+pub mod curvature;
 ///Deformation fields to do the vertex manipulation
 pub mod deformation;
 mod integrations;
 /// Ray casting onto mesh graphs
 pub mod ray;
+/// Remeshing operations that work on the whole mesh graph
+pub mod remesh;
 /// Selection strategies to decide which vertices to deform
 pub mod selectors;
+/// Conversion between world space and the local space of a mesh graph
+pub mod transform;
+mod utils;
+
+/// Defines how the target edge length of the dynamic topology is determined.
+///
+/// This is similar to the "Detailing" options of Blender's Dyntopo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DetailMode {
+    /// The maximum edge length is given in world units. It stays the same regardless of brush size or zoom level.
+    Constant { max_edge_length: f32 },
+
+    /// The maximum edge length is a fraction of the brush radius.
+    ///
+    /// - **`brush_radius`** is the radius of the brush in world units.
+    /// - **`detail`** is the maximum edge length relative to the brush radius, e.g. `0.25` means
+    ///   that edges are at most a quarter of the brush radius long.
+    BrushRelative { brush_radius: f32, detail: f32 },
+
+    /// The maximum edge length is given in screen pixels and converted to world units
+    /// at the distance of the camera to the sculpted point.
+    ///
+    /// - **`pixel_size`** is the maximum edge length in pixels.
+    /// - **`camera_distance`** is the distance from the camera to the sculpted point in world units.
+    /// - **`fov_y`** is the vertical field of view of the (perspective) camera in radians.
+    /// - **`viewport_height`** is the height of the viewport in pixels.
+    ScreenSpace {
+        pixel_size: f32,
+        camera_distance: f32,
+        fov_y: f32,
+        viewport_height: f32,
+    },
+}
+
+impl DetailMode {
+    /// Computes the maximum edge length in world units for this detail mode.
+    pub fn max_edge_length(&self) -> f32 {
+        match *self {
+            DetailMode::Constant { max_edge_length } => max_edge_length,
+            DetailMode::BrushRelative {
+                brush_radius,
+                detail,
+            } => brush_radius * detail,
+            DetailMode::ScreenSpace {
+                pixel_size,
+                camera_distance,
+                fov_y,
+                viewport_height,
+            } => {
+                let world_per_pixel =
+                    2.0 * camera_distance * (fov_y * 0.5).tan() / viewport_height.max(1.0);
+
+                pixel_size * world_per_pixel
+            }
+        }
+    }
+}
+
+/// Defines which dynamic topology operations are done while deforming.
+///
+/// This is similar to the "Refine Method" of Blender's Dyntopo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TopologyMode {
+    /// Edges that are too long are split and edges that are too short are collapsed.
+    #[default]
+    SubdivideCollapse,
+
+    /// Edges that are too long are split. Existing detail is never removed.
+    Subdivide,
+
+    /// Edges that are too short are collapsed. No new detail is added.
+    Collapse,
+
+    /// The topology isn't changed at all, only vertices are moved.
+    /// Use this for meshes with fixed topology like morph targets or UV-mapped meshes.
+    Fixed,
+}
+
+impl TopologyMode {
+    /// Returns `true` if edges that are too long are split.
+    #[inline]
+    pub fn subdivides(&self) -> bool {
+        matches!(self, Self::SubdivideCollapse | Self::Subdivide)
+    }
+
+    /// Returns `true` if edges that are too short are collapsed.
+    #[inline]
+    pub fn collapses(&self) -> bool {
+        matches!(self, Self::SubdivideCollapse | Self::Collapse)
+    }
+}
 
 /// Defines all the necessary parameters for sculpting operations.
 #[derive(Debug, Clone, Copy)]
@@ -41,6 +148,20 @@ pub struct SculptParams {
     pub max_move_dist_squared: f32,
     pub min_edge_length_squared: f32,
     pub max_edge_length_squared: f32,
+    /// What to do when the deformed part of the mesh penetrates other parts of the mesh.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub self_collision: SelfCollisionMode,
+    /// Keeps the enclosed volume of the mesh constant while deforming. This counteracts the
+    /// shrinking caused by smoothing or pinching. It only makes sense for closed meshes.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub preserve_volume: bool,
+    /// Which dynamic topology operations are done while deforming.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub topology_mode: TopologyMode,
+    /// Edges of the selection with a dihedral angle (in radians) above this are treated as feature edges
+    /// that are never collapsed. See [`MeshAttributes`](crate::attributes::MeshAttributes) for tagging them manually.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub feature_angle: Option<f32>,
 }
 
 impl SculptParams {
@@ -54,9 +175,84 @@ impl SculptParams {
             max_move_dist_squared: max_edge_length_squared * 0.11,
             min_edge_length_squared: max_edge_length_squared * 0.24,
             max_edge_length_squared,
+            self_collision: SelfCollisionMode::Off,
+            preserve_volume: false,
+            topology_mode: TopologyMode::SubdivideCollapse,
+            feature_angle: None,
         }
     }
 
+    /// Returns a copy of these parameters with the given self-collision mode.
+    pub const fn with_self_collision(mut self, self_collision: SelfCollisionMode) -> Self {
+        self.self_collision = self_collision;
+        self
+    }
+
+    /// Returns a copy of these parameters with volume preservation turned on or off.
+    pub const fn with_preserve_volume(mut self, preserve_volume: bool) -> Self {
+        self.preserve_volume = preserve_volume;
+        self
+    }
+
+    /// Returns a copy of these parameters with the given topology mode.
+    pub const fn with_topology_mode(mut self, topology_mode: TopologyMode) -> Self {
+        self.topology_mode = topology_mode;
+        self
+    }
+
+    /// Returns a copy of these parameters that detects feature edges sharper than `feature_angle` (in radians).
+    pub const fn with_feature_angle(mut self, feature_angle: f32) -> Self {
+        self.feature_angle = Some(feature_angle);
+        self
+    }
+
+    /// Converts the lengths of these parameters from world space to the local space of a mesh graph
+    /// placed by `transform`.
+    ///
+    /// Use this if the parameters are created from world units, e.g. with [`SculptParams::screen_space`].
+    pub fn to_local(mut self, transform: &MeshTransform) -> Self {
+        let scale_squared = transform.scale().powi(2);
+
+        self.max_move_dist_squared /= scale_squared;
+        self.min_edge_length_squared /= scale_squared;
+        self.max_edge_length_squared /= scale_squared;
+        self
+    }
+
+    /// Creates a new instance of `SculptParams` with the maximum edge length given by the detail mode.
+    ///
+    /// For the relative detail modes this should be called again whenever the brush radius
+    /// or the camera changes, e.g. every frame before calling
+    /// [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes).
+    pub fn from_detail_mode(detail_mode: DetailMode) -> Self {
+        Self::new(detail_mode.max_edge_length())
+    }
+
+    /// Shorthand for [`SculptParams::from_detail_mode`] with [`DetailMode::BrushRelative`].
+    #[inline]
+    pub fn brush_relative(brush_radius: f32, detail: f32) -> Self {
+        Self::from_detail_mode(DetailMode::BrushRelative {
+            brush_radius,
+            detail,
+        })
+    }
+
+    /// Shorthand for [`SculptParams::from_detail_mode`] with [`DetailMode::ScreenSpace`].
+    #[inline]
+    pub fn screen_space(
+        pixel_size: f32,
+        camera_distance: f32,
+        fov_y: f32,
+        viewport_height: f32,
+    ) -> Self {
+        Self::from_detail_mode(DetailMode::ScreenSpace {
+            pixel_size,
+            camera_distance,
+            fov_y,
+            viewport_height,
+        })
+    }
+
     pub fn from_mesh_graph(mesh_graph: &MeshGraph) -> Self {
         let mut edge_length = 0.0;
 
@@ -6,6 +6,8 @@ use parry3d::{
 
 use mesh_graph::{Face, MeshGraph};
 
+use crate::transform::MeshTransform;
+
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ray {
@@ -38,6 +40,20 @@ impl Ray {
             face,
         })
     }
+
+    /// Same as [`Ray::cast_ray_and_get_face_id`] but for a mesh graph that is placed in the world by `transform`.
+    ///
+    /// This ray is given in world space. The returned intersection is in the local space of the mesh graph
+    /// so it can be passed on to selectors and deformation fields directly.
+    pub fn cast_ray_and_get_face_id_with_transform(
+        self,
+        mesh_graph: &MeshGraph,
+        transform: &MeshTransform,
+    ) -> Option<FaceIntersection> {
+        transform
+            .ray_to_local(self)
+            .cast_ray_and_get_face_id(mesh_graph)
+    }
 }
 
 impl From<Ray> for parry3d::query::Ray {
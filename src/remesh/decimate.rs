@@ -0,0 +1,519 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::{DMat3, DVec3, Vec3};
+use hashbrown::HashSet;
+use mesh_graph::{HalfedgeId, MeshGraph, VertexId, error_none};
+use slotmap::SecondaryMap;
+use tracing::{error, instrument};
+
+use crate::utils::{face_area_normal, is_boundary_vertex, rebuild_bvh_from_scratch};
+
+/// Edges with a dihedral angle above this (in radians) are treated as sharp features.
+const FEATURE_ANGLE: f32 = std::f32::consts::FRAC_PI_4;
+
+/// How strongly sharp features are preserved compared to flat areas.
+const FEATURE_WEIGHT: f64 = 10.0;
+
+/// Defines how far a mesh should be decimated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DecimationTarget {
+    /// Decimate until the mesh has at most this many faces.
+    FaceCount(usize),
+
+    /// Decimate until the number of faces is reduced to this fraction of the original face count,
+    /// e.g. `0.25` keeps a quarter of the faces.
+    Ratio(f32),
+}
+
+/// Reduces the number of faces of the mesh graph by collapsing edges in the order of the least
+/// geometric error using quadric error metrics as described by
+/// [Garland and Heckbert](https://www.cs.cmu.edu/~garland/Papers/quadrics.pdf).
+///
+/// This keeps detail where it matters: flat areas are decimated first while curved areas keep more faces.
+/// Sharp edges are preserved by additional constraints and boundary vertices are never moved.
+/// Collapses that would fold over faces or make the mesh non-manifold are skipped, so the target might
+/// not be reached for some meshes.
+#[instrument(skip(mesh_graph))]
+pub fn decimate(mesh_graph: &mut MeshGraph, target: DecimationTarget) {
+    let target_face_count = match target {
+        DecimationTarget::FaceCount(count) => count,
+        DecimationTarget::Ratio(ratio) => {
+            (mesh_graph.faces.len() as f32 * ratio.clamp(0.0, 1.0)).round() as usize
+        }
+    }
+    // a tetrahedron is the smallest closed mesh
+    .max(4);
+
+    let mut decimator = Decimator::new(mesh_graph);
+
+    while mesh_graph.faces.len() > target_face_count {
+        let Some(candidate) = decimator.queue.pop() else {
+            break;
+        };
+
+        if !decimator.is_up_to_date(mesh_graph, &candidate) {
+            continue;
+        }
+
+        decimator.collapse(mesh_graph, candidate);
+    }
+
+    if mesh_graph.vertex_normals.is_some() {
+        mesh_graph.compute_vertex_normals();
+    }
+    rebuild_bvh_from_scratch(mesh_graph);
+}
+
+/// Symmetric 4x4 matrix that measures the sum of squared distances to a set of planes.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric {
+    a: DMat3,
+    b: DVec3,
+    c: f64,
+}
+
+impl Quadric {
+    fn from_plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let d = -normal.dot(point);
+
+        Self {
+            a: DMat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z) * weight,
+            b: normal * d * weight,
+            c: d * d * weight,
+        }
+    }
+
+    fn error(&self, pos: DVec3) -> f64 {
+        pos.dot(self.a * pos) + 2.0 * self.b.dot(pos) + self.c
+    }
+
+    /// Returns the position with the least error. If that can't be computed reliably,
+    /// the best of the given fallback positions is used.
+    fn optimal_position(&self, fallbacks: [DVec3; 3]) -> (DVec3, f64) {
+        if self.a.determinant().abs() > 1e-10 {
+            let pos = -(self.a.inverse() * self.b);
+
+            if pos.is_finite() {
+                return (pos, self.error(pos));
+            }
+        }
+
+        fallbacks
+            .into_iter()
+            .map(|pos| (pos, self.error(pos)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap_or_default()
+    }
+}
+
+impl std::ops::Add for Quadric {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            a: self.a + other.a,
+            b: self.b + other.b,
+            c: self.c + other.c,
+        }
+    }
+}
+
+impl std::ops::AddAssign for Quadric {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+/// An edge collapse in the priority queue.
+#[derive(Debug, Clone, Copy)]
+struct CollapseCandidate {
+    cost: f64,
+    halfedge: HalfedgeId,
+    start_vertex: VertexId,
+    end_vertex: VertexId,
+    start_version: u32,
+    end_version: u32,
+    position: Vec3,
+}
+
+impl PartialEq for CollapseCandidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for CollapseCandidate {}
+
+impl PartialOrd for CollapseCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CollapseCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so the `BinaryHeap` pops the cheapest collapse first
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+struct Decimator {
+    quadrics: SecondaryMap<VertexId, Quadric>,
+    /// Incremented every time a vertex changes so outdated candidates in the queue can be detected.
+    versions: SecondaryMap<VertexId, u32>,
+    queue: BinaryHeap<CollapseCandidate>,
+}
+
+impl Decimator {
+    fn new(mesh_graph: &MeshGraph) -> Self {
+        let mut quadrics = SecondaryMap::with_capacity(mesh_graph.vertices.len());
+        let mut versions = SecondaryMap::with_capacity(mesh_graph.vertices.len());
+
+        for vertex_id in mesh_graph.vertices.keys() {
+            quadrics.insert(vertex_id, Quadric::default());
+            versions.insert(vertex_id, 0);
+        }
+
+        for (face_id, face) in &mesh_graph.faces {
+            let Some(area_normal) = face_area_normal(face_id, mesh_graph) else {
+                continue;
+            };
+            let Some(normal) = area_normal.try_normalize() else {
+                continue;
+            };
+
+            for vertex_id in face.vertices(mesh_graph) {
+                let Some(pos) = mesh_graph.positions.get(vertex_id) else {
+                    error!("Vertex position not found");
+                    continue;
+                };
+
+                let quadric = Quadric::from_plane(
+                    normal.as_dvec3(),
+                    pos.as_dvec3(),
+                    area_normal.length() as f64 * 0.5,
+                );
+
+                if let Some(q) = quadrics.get_mut(vertex_id) {
+                    *q += quadric;
+                }
+            }
+        }
+
+        let mut decimator = Self {
+            quadrics,
+            versions,
+            queue: BinaryHeap::new(),
+        };
+
+        decimator.add_feature_constraints(mesh_graph);
+
+        let mut visited = HashSet::new();
+
+        for (he_id, he) in &mesh_graph.halfedges {
+            if let Some(twin) = he.twin
+                && visited.contains(&twin)
+            {
+                continue;
+            }
+            visited.insert(he_id);
+
+            decimator.push_candidate(mesh_graph, he_id);
+        }
+
+        decimator
+    }
+
+    /// Adds planes perpendicular to the faces along sharp edges so vertices stay on them.
+    fn add_feature_constraints(&mut self, mesh_graph: &MeshGraph) {
+        let cos_feature_angle = FEATURE_ANGLE.cos();
+
+        for he in mesh_graph.halfedges.values() {
+            let (Some(face_id), Some(twin_id)) = (he.face, he.twin) else {
+                continue;
+            };
+            let Some(twin_face_id) = mesh_graph.halfedges.get(twin_id).and_then(|t| t.face) else {
+                continue;
+            };
+
+            let (Some(normal), Some(twin_normal)) = (
+                face_area_normal(face_id, mesh_graph).and_then(|n| n.try_normalize()),
+                face_area_normal(twin_face_id, mesh_graph).and_then(|n| n.try_normalize()),
+            ) else {
+                continue;
+            };
+
+            if normal.dot(twin_normal) > cos_feature_angle {
+                continue;
+            }
+
+            let Some(start_vertex) = he.start_vertex(mesh_graph) else {
+                continue;
+            };
+            let (Some(start), Some(end)) = (
+                mesh_graph.positions.get(start_vertex),
+                mesh_graph.positions.get(he.end_vertex),
+            ) else {
+                error!("Edge positions not found");
+                continue;
+            };
+
+            let edge = end - start;
+            let Some(constraint_normal) = edge.cross(normal).try_normalize() else {
+                continue;
+            };
+
+            // every edge is visited twice (once per halfedge), so every adjacent face adds its constraint once
+            let quadric = Quadric::from_plane(
+                constraint_normal.as_dvec3(),
+                start.as_dvec3(),
+                FEATURE_WEIGHT * edge.length_squared() as f64,
+            );
+
+            for vertex_id in [start_vertex, he.end_vertex] {
+                if let Some(q) = self.quadrics.get_mut(vertex_id) {
+                    *q += quadric;
+                }
+            }
+        }
+    }
+
+    fn push_candidate(&mut self, mesh_graph: &MeshGraph, he_id: HalfedgeId) {
+        let Some(he) = mesh_graph.halfedges.get(he_id) else {
+            error!("Halfedge not found");
+            return;
+        };
+        let Some(start_vertex) = he.start_vertex(mesh_graph) else {
+            return;
+        };
+        let end_vertex = he.end_vertex;
+
+        if is_boundary_vertex(start_vertex, mesh_graph)
+            || is_boundary_vertex(end_vertex, mesh_graph)
+        {
+            return;
+        }
+
+        let (Some(start), Some(end)) = (
+            mesh_graph.positions.get(start_vertex),
+            mesh_graph.positions.get(end_vertex),
+        ) else {
+            error!("Edge positions not found");
+            return;
+        };
+
+        let (Some(q_start), Some(q_end)) = (
+            self.quadrics.get(start_vertex),
+            self.quadrics.get(end_vertex),
+        ) else {
+            error!("Quadrics not found");
+            return;
+        };
+
+        let (start, end) = (start.as_dvec3(), end.as_dvec3());
+        let (position, cost) =
+            (*q_start + *q_end).optimal_position([start, end, (start + end) * 0.5]);
+
+        self.queue.push(CollapseCandidate {
+            cost,
+            halfedge: he_id,
+            start_vertex,
+            end_vertex,
+            start_version: self.versions.get(start_vertex).copied().unwrap_or_default(),
+            end_version: self.versions.get(end_vertex).copied().unwrap_or_default(),
+            position: position.as_vec3(),
+        });
+    }
+
+    /// Checks that the candidate still describes the current state of the mesh.
+    fn is_up_to_date(&self, mesh_graph: &MeshGraph, candidate: &CollapseCandidate) -> bool {
+        let Some(he) = mesh_graph.halfedges.get(candidate.halfedge) else {
+            return false;
+        };
+
+        he.end_vertex == candidate.end_vertex
+            && he.start_vertex(mesh_graph) == Some(candidate.start_vertex)
+            && self.versions.get(candidate.start_vertex) == Some(&candidate.start_version)
+            && self.versions.get(candidate.end_vertex) == Some(&candidate.end_version)
+    }
+
+    fn collapse(&mut self, mesh_graph: &mut MeshGraph, candidate: CollapseCandidate) {
+        if !can_collapse(mesh_graph, &candidate) {
+            return;
+        }
+
+        let CollapseCandidate {
+            halfedge,
+            start_vertex,
+            end_vertex,
+            position,
+            ..
+        } = candidate;
+
+        let (Some(q_start), Some(q_end)) = (
+            self.quadrics.get(start_vertex).copied(),
+            self.quadrics.get(end_vertex).copied(),
+        ) else {
+            error!("Quadrics not found");
+            return;
+        };
+
+        let (removed_vertices, _, _) = mesh_graph.collapse_edge(halfedge);
+
+        for vertex_id in removed_vertices {
+            self.quadrics.remove(vertex_id);
+            self.versions.remove(vertex_id);
+        }
+
+        let Some(pos) = mesh_graph.positions.get_mut(start_vertex) else {
+            // the start vertex was removed by the cleanup of the collapse
+            return;
+        };
+        *pos = position;
+
+        self.quadrics.insert(start_vertex, q_start + q_end);
+
+        let Some(vertex) = mesh_graph
+            .vertices
+            .get(start_vertex)
+            .or_else(error_none!("Start vertex not found"))
+        else {
+            return;
+        };
+
+        let outgoing_halfedges = vertex.outgoing_halfedges(mesh_graph).collect::<Vec<_>>();
+
+        // the collapse might have moved the neighbours as well
+        let mut changed_vertices = vec![start_vertex];
+        changed_vertices.extend(vertex.neighbours(mesh_graph));
+
+        for vertex_id in changed_vertices {
+            if let Some(version) = self.versions.get_mut(vertex_id) {
+                *version += 1;
+            }
+        }
+
+        for he_id in outgoing_halfedges {
+            self.push_candidate(mesh_graph, he_id);
+
+            // also update the edges of the neighbours as their versions changed
+            let Some(neighbour) = mesh_graph
+                .halfedges
+                .get(he_id)
+                .and_then(|he| mesh_graph.vertices.get(he.end_vertex))
+            else {
+                continue;
+            };
+
+            for neighbour_he_id in neighbour.outgoing_halfedges(mesh_graph).collect::<Vec<_>>() {
+                self.push_candidate(mesh_graph, neighbour_he_id);
+            }
+        }
+    }
+}
+
+/// Checks that collapsing the edge keeps the mesh manifold and doesn't fold over any faces.
+fn can_collapse(mesh_graph: &MeshGraph, candidate: &CollapseCandidate) -> bool {
+    let (Some(start), Some(end)) = (
+        mesh_graph.vertices.get(candidate.start_vertex),
+        mesh_graph.vertices.get(candidate.end_vertex),
+    ) else {
+        return false;
+    };
+
+    // link condition: the two vertices may only share the two vertices opposite of the edge
+    let start_neighbours = start.neighbours(mesh_graph).collect::<HashSet<_>>();
+    let common_neighbours = end
+        .neighbours(mesh_graph)
+        .filter(|n| start_neighbours.contains(n))
+        .collect::<Vec<_>>();
+
+    if common_neighbours.len() != 2 {
+        return false;
+    }
+
+    // the opposite vertices lose one edge. With less than three left, two faces would share all vertices.
+    if common_neighbours.into_iter().any(|v_id| {
+        mesh_graph
+            .vertices
+            .get(v_id)
+            .is_none_or(|v| v.degree(mesh_graph) <= 3)
+    }) {
+        return false;
+    }
+
+    // `collapse_edge` detects flaps by comparing positions, so coincident vertices around the edge
+    // (e.g. from symmetric collapses) would make it remove the wrong faces.
+    let (Some(&start_pos), Some(&end_pos)) = (
+        mesh_graph.positions.get(candidate.start_vertex),
+        mesh_graph.positions.get(candidate.end_vertex),
+    ) else {
+        return false;
+    };
+
+    let ring = start_neighbours
+        .iter()
+        .copied()
+        .chain(end.neighbours(mesh_graph))
+        .filter(|&v_id| v_id != candidate.start_vertex && v_id != candidate.end_vertex)
+        .collect::<HashSet<_>>();
+
+    let mut ring_positions = vec![(start_pos + end_pos) * 0.5, candidate.position];
+    ring_positions.extend(
+        ring.into_iter()
+            .filter_map(|v_id| mesh_graph.positions.get(v_id).copied()),
+    );
+
+    for (i, pos) in ring_positions.iter().enumerate() {
+        if ring_positions[i + 1..].contains(pos) {
+            return false;
+        }
+    }
+
+    for (vertex_id, vertex) in [(candidate.start_vertex, start), (candidate.end_vertex, end)] {
+        for face_id in vertex.faces(mesh_graph) {
+            let Some(face) = mesh_graph.faces.get(face_id) else {
+                continue;
+            };
+
+            let vertices = face.vertices(mesh_graph).collect::<Vec<_>>();
+
+            // these faces are removed by the collapse
+            if vertices.contains(&candidate.start_vertex)
+                && vertices.contains(&candidate.end_vertex)
+            {
+                continue;
+            }
+
+            let Some(old_normal) = face_area_normal(face_id, mesh_graph) else {
+                continue;
+            };
+
+            let moved = vertices
+                .iter()
+                .filter_map(|&v| {
+                    if v == vertex_id {
+                        Some(candidate.position)
+                    } else {
+                        mesh_graph.positions.get(v).copied()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let [a, b, c] = moved[..] else {
+                return false;
+            };
+
+            let new_normal = (b - a).cross(c - a);
+
+            if new_normal.dot(old_normal) <= 0.0
+                || new_normal.length_squared() < old_normal.length_squared() * 1e-6
+            {
+                return false;
+            }
+        }
+    }
+
+    true
+}
@@ -0,0 +1,206 @@
+use hashbrown::HashSet;
+use mesh_graph::{HalfedgeId, MeshGraph, VertexId, error_none};
+use tracing::{error, instrument};
+
+use crate::utils::{face_area_normal, is_boundary_vertex};
+
+/// Flips the edge of the given halfedge.
+///
+/// The edge between the two triangles `(a, b, c)` and `(b, a, d)` is replaced by the edge between `c` and `d`.
+/// The flip is rejected if either side of the edge is a boundary, if the edge `c-d` already exists,
+/// if `a` or `b` would end up with a degree below three or if one of the new triangles would be folded over.
+///
+/// Returns `true` if the edge was flipped.
+#[instrument(skip(mesh_graph))]
+pub fn flip_edge(mesh_graph: &mut MeshGraph, halfedge_id: HalfedgeId) -> bool {
+    flip_edge_inner(mesh_graph, halfedge_id).is_some()
+}
+
+fn flip_edge_inner(mesh_graph: &mut MeshGraph, h_id: HalfedgeId) -> Option<()> {
+    let h = *mesh_graph
+        .halfedges
+        .get(h_id)
+        .or_else(error_none!("Halfedge not found"))?;
+    let t_id = h.twin?;
+    let t = *mesh_graph
+        .halfedges
+        .get(t_id)
+        .or_else(error_none!("Twin not found"))?;
+
+    let face_id = h.face?;
+    let twin_face_id = t.face?;
+
+    let h1_id = h.next.or_else(error_none!("Next missing"))?;
+    let h2_id = mesh_graph
+        .halfedges
+        .get(h1_id)
+        .or_else(error_none!("Next not found"))?
+        .next
+        .or_else(error_none!("Next next missing"))?;
+    let t1_id = t.next.or_else(error_none!("Twin next missing"))?;
+    let t2_id = mesh_graph
+        .halfedges
+        .get(t1_id)
+        .or_else(error_none!("Twin next not found"))?
+        .next
+        .or_else(error_none!("Twin next next missing"))?;
+
+    let a = t.end_vertex;
+    let b = h.end_vertex;
+    let c = mesh_graph.halfedges[h1_id].end_vertex;
+    let d = mesh_graph.halfedges[t1_id].end_vertex;
+
+    if c == d {
+        return None;
+    }
+
+    let vertex_a = *mesh_graph
+        .vertices
+        .get(a)
+        .or_else(error_none!("Vertex a not found"))?;
+    let vertex_b = *mesh_graph
+        .vertices
+        .get(b)
+        .or_else(error_none!("Vertex b not found"))?;
+    let vertex_c = mesh_graph
+        .vertices
+        .get(c)
+        .or_else(error_none!("Vertex c not found"))?;
+
+    if vertex_a.degree(mesh_graph) <= 3 || vertex_b.degree(mesh_graph) <= 3 {
+        return None;
+    }
+
+    if vertex_c.neighbours(mesh_graph).any(|n| n == d) {
+        return None;
+    }
+
+    let pos = |v: VertexId| mesh_graph.positions.get(v).copied();
+    let (pa, pb, pc, pd) = (pos(a)?, pos(b)?, pos(c)?, pos(d)?);
+
+    let old_normal =
+        face_area_normal(face_id, mesh_graph)? + face_area_normal(twin_face_id, mesh_graph)?;
+    let new_normal_1 = (pa - pc).cross(pd - pc);
+    let new_normal_2 = (pb - pd).cross(pc - pd);
+
+    if new_normal_1.dot(old_normal) <= 0.0 || new_normal_2.dot(old_normal) <= 0.0 {
+        return None;
+    }
+
+    // face (d, c, a)
+    mesh_graph.halfedges[h_id].end_vertex = c;
+    mesh_graph.halfedges[h_id].next = Some(h2_id);
+    mesh_graph.halfedges[h2_id].next = Some(t1_id);
+    mesh_graph.halfedges[t1_id].next = Some(h_id);
+    mesh_graph.halfedges[t1_id].face = Some(face_id);
+
+    // face (c, d, b)
+    mesh_graph.halfedges[t_id].end_vertex = d;
+    mesh_graph.halfedges[t_id].next = Some(t2_id);
+    mesh_graph.halfedges[t2_id].next = Some(h1_id);
+    mesh_graph.halfedges[h1_id].next = Some(t_id);
+    mesh_graph.halfedges[h1_id].face = Some(twin_face_id);
+
+    mesh_graph.faces[face_id].halfedge = h_id;
+    mesh_graph.faces[twin_face_id].halfedge = t_id;
+
+    if vertex_a.outgoing_halfedge == Some(h_id) {
+        mesh_graph.vertices[a].outgoing_halfedge = Some(t1_id);
+    }
+    if vertex_b.outgoing_halfedge == Some(t_id) {
+        mesh_graph.vertices[b].outgoing_halfedge = Some(h1_id);
+    }
+
+    for face_id in [face_id, twin_face_id] {
+        let face = mesh_graph.faces[face_id];
+        mesh_graph
+            .bvh
+            .insert_or_update_partially(face.aabb(mesh_graph), face.index, 0.0);
+    }
+
+    Some(())
+}
+
+/// Flips all edges of the mesh graph that bring the degrees of their four adjacent vertices
+/// closer to the optimal degree (6 for inner vertices, 4 for boundary vertices).
+///
+/// This improves the shape of the triangles after splitting and collapsing edges.
+///
+/// Returns the number of flipped edges.
+#[instrument(skip(mesh_graph))]
+pub fn flip_edges_to_improve_degree(mesh_graph: &mut MeshGraph) -> usize {
+    let halfedge_ids = mesh_graph.halfedges.keys().collect::<Vec<_>>();
+    flip_edges_to_improve_degree_of_halfedges(mesh_graph, &halfedge_ids)
+}
+
+/// Same as [`flip_edges_to_improve_degree`] but only for the edges of the given halfedges.
+#[instrument(skip(mesh_graph, halfedge_ids))]
+pub fn flip_edges_to_improve_degree_of_halfedges(
+    mesh_graph: &mut MeshGraph,
+    halfedge_ids: &[HalfedgeId],
+) -> usize {
+    let mut flipped = 0;
+    let mut visited = HashSet::new();
+
+    for &he_id in halfedge_ids {
+        let Some(he) = mesh_graph.halfedges.get(he_id) else {
+            continue;
+        };
+
+        // only look at one of the two twin halfedges
+        if !visited.insert(he_id) {
+            continue;
+        }
+        if let Some(twin) = he.twin {
+            visited.insert(twin);
+        }
+
+        let Some(deviation_before) = degree_deviation(mesh_graph, he_id, 0) else {
+            continue;
+        };
+        let Some(deviation_after) = degree_deviation(mesh_graph, he_id, 1) else {
+            continue;
+        };
+
+        if deviation_after < deviation_before && flip_edge(mesh_graph, he_id) {
+            flipped += 1;
+        }
+    }
+
+    flipped
+}
+
+/// Sum of the squared differences of the degrees of the four vertices around an edge to their optimal degree.
+/// With `flip == 1` the deviation is computed as if the edge was flipped.
+fn degree_deviation(mesh_graph: &MeshGraph, he_id: HalfedgeId, flip: i32) -> Option<i32> {
+    let he = mesh_graph.halfedges.get(he_id)?;
+    let twin = mesh_graph.halfedges.get(he.twin?)?;
+
+    let c = mesh_graph.halfedges.get(he.next?)?.end_vertex;
+    let d = mesh_graph.halfedges.get(twin.next?)?.end_vertex;
+
+    let mut deviation = 0;
+
+    for (vertex_id, change) in [
+        (he.end_vertex, -flip),
+        (twin.end_vertex, -flip),
+        (c, flip),
+        (d, flip),
+    ] {
+        let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+            error!("Vertex not found");
+            return None;
+        };
+
+        let optimal = if is_boundary_vertex(vertex_id, mesh_graph) {
+            4
+        } else {
+            6
+        };
+
+        let diff = vertex.degree(mesh_graph) as i32 + change - optimal;
+        deviation += diff * diff;
+    }
+
+    Some(deviation)
+}
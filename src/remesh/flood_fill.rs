@@ -0,0 +1,51 @@
+use mesh_graph::MeshGraph;
+use tracing::instrument;
+
+use crate::{SculptParams, utils::rebuild_bvh_from_scratch};
+
+use super::remesh_iteration;
+
+/// Maximum number of split/collapse/flip/relax rounds done by [`detail_flood_fill`].
+const MAX_FLOOD_FILL_ITERATIONS: usize = 20;
+
+/// Remeshes the whole mesh graph until all edges are within the bounds given by `params`.
+///
+/// This is the same as Blender's "Detail Flood Fill". It is useful for meshes with wildly varying
+/// triangle sizes (e.g. after importing) before sculpting on them.
+///
+/// Every round splits all edges that are too long, collapses all edges that are too short, flips
+/// edges to even out vertex degrees and finally relaxes the vertices tangentially. This is repeated
+/// until all edges are within bounds or a maximum number of rounds is reached.
+///
+/// Returns `true` if all edges are within bounds afterwards.
+#[instrument(skip(mesh_graph))]
+pub fn detail_flood_fill(mesh_graph: &mut MeshGraph, params: SculptParams) -> bool {
+    let mut within_bounds = false;
+
+    for _ in 0..MAX_FLOOD_FILL_ITERATIONS {
+        remesh_iteration(
+            mesh_graph,
+            params.min_edge_length_squared,
+            params.max_edge_length_squared,
+        );
+
+        within_bounds = all_edges_within_bounds(mesh_graph, params);
+        if within_bounds {
+            break;
+        }
+    }
+
+    if mesh_graph.vertex_normals.is_some() {
+        mesh_graph.compute_vertex_normals();
+    }
+    rebuild_bvh_from_scratch(mesh_graph);
+
+    within_bounds
+}
+
+fn all_edges_within_bounds(mesh_graph: &MeshGraph, params: SculptParams) -> bool {
+    mesh_graph.halfedges.values().all(|he| {
+        let len_sqr = he.length_squared(mesh_graph);
+        len_sqr >= params.min_edge_length_squared && len_sqr <= params.max_edge_length_squared
+    })
+}
@@ -0,0 +1,684 @@
+use glam::{DVec3, Vec3};
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{
+    FaceId, Halfedge, HalfedgeId, MeshGraph, Selection, SelectionOps, VertexId, error_none,
+};
+use tracing::{error, instrument};
+
+use crate::{SculptParams, utils::rebuild_bvh_from_scratch};
+
+use super::{flip_edges_to_improve_degree_of_halfedges, tangential_relaxation_of_vertices};
+
+/// Number of split/flip/relax rounds used to refine a filled hole.
+const REFINE_ITERATIONS: usize = 3;
+
+/// Maximum number of solver iterations used by [`HoleFillMode::Fair`].
+const MAX_FAIRING_ITERATIONS: usize = 1000;
+
+/// Relative residual at which the solver of [`HoleFillMode::Fair`] stops.
+const FAIRING_TOLERANCE: f64 = 1e-8;
+
+/// Defines the shape of the surface that fills a hole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HoleFillMode {
+    /// Spans the hole with the triangulation of minimal area. Planar holes are filled flat.
+    #[default]
+    MinimumArea,
+
+    /// Like `MinimumArea` but the inner vertices of the patch are smoothed afterwards so the patch
+    /// continues the curvature of the surrounding surface.
+    Fair,
+}
+
+/// Finds all holes of the mesh graph.
+///
+/// Every hole is returned as a loop of boundary halfedges, i.e. halfedges that have a face
+/// but whose twin is missing or has no face. The halfedges are ordered along the boundary.
+#[instrument(skip(mesh_graph))]
+pub fn boundary_loops(mesh_graph: &MeshGraph) -> Vec<Vec<HalfedgeId>> {
+    let mut loops = Vec::new();
+    let mut visited = HashSet::new();
+
+    for (he_id, he) in &mesh_graph.halfedges {
+        if visited.contains(&he_id) || !is_boundary_halfedge(he, mesh_graph) {
+            continue;
+        }
+
+        if let Some(boundary_loop) = boundary_loop(mesh_graph, he_id) {
+            visited.extend(boundary_loop.iter().copied());
+            loops.push(boundary_loop);
+        } else {
+            error!("Boundary is not closed");
+            visited.insert(he_id);
+        }
+    }
+
+    loops
+}
+
+/// Fills all holes of the mesh graph. See [`fill_hole`] for details.
+///
+/// This also closes openings that are intended, like the border of a plane. Use [`boundary_loops`]
+/// together with [`fill_hole`] to only fill some of the holes, e.g. the ones with few edges.
+///
+/// Returns the number of holes that were filled.
+#[instrument(skip(mesh_graph))]
+pub fn fill_holes(mesh_graph: &mut MeshGraph, params: SculptParams, mode: HoleFillMode) -> usize {
+    let mut filled = 0;
+
+    for boundary_loop in boundary_loops(mesh_graph) {
+        if fill_hole_inner(mesh_graph, boundary_loop[0], params, mode) {
+            filled += 1;
+        }
+    }
+
+    finish(mesh_graph);
+
+    filled
+}
+
+/// Fills the hole that the given boundary halfedge belongs to.
+///
+/// The hole is first triangulated with the triangulation of minimal area. The resulting patch is then
+/// refined so that its edges match the edge lengths of `params` and, depending on `mode`, smoothed.
+/// The vertices of the boundary are never moved. Boundary edges may be split to match the edge lengths,
+/// but the new vertices stay on them and no edges outside of the patch are flipped, so the surrounding
+/// surface keeps its shape.
+///
+/// Returns `true` if the hole was filled.
+#[instrument(skip(mesh_graph))]
+pub fn fill_hole(
+    mesh_graph: &mut MeshGraph,
+    boundary_halfedge: HalfedgeId,
+    params: SculptParams,
+    mode: HoleFillMode,
+) -> bool {
+    let filled = fill_hole_inner(mesh_graph, boundary_halfedge, params, mode);
+
+    if filled {
+        finish(mesh_graph);
+    }
+
+    filled
+}
+
+fn finish(mesh_graph: &mut MeshGraph) {
+    if mesh_graph.vertex_normals.is_some() {
+        mesh_graph.compute_vertex_normals();
+    }
+    rebuild_bvh_from_scratch(mesh_graph);
+}
+
+fn fill_hole_inner(
+    mesh_graph: &mut MeshGraph,
+    boundary_halfedge: HalfedgeId,
+    params: SculptParams,
+    mode: HoleFillMode,
+) -> bool {
+    let Some(boundary_loop) = boundary_loop(mesh_graph, boundary_halfedge) else {
+        error!("Halfedge is not part of a closed boundary");
+        return false;
+    };
+
+    let original_vertices = mesh_graph.vertices.keys().collect::<HashSet<_>>();
+
+    let Some(patch_faces) = triangulate_hole(mesh_graph, &boundary_loop) else {
+        return false;
+    };
+
+    let mut selection = Selection::default();
+    for face_id in patch_faces {
+        selection.insert(face_id);
+    }
+
+    for _ in 0..REFINE_ITERATIONS {
+        subdivide_patch(mesh_graph, params.max_edge_length_squared, &mut selection);
+
+        let halfedge_ids = selection
+            .resolve_to_halfedges(mesh_graph)
+            .into_iter()
+            .filter(|&he_id| is_patch_interior(mesh_graph, &selection, he_id))
+            .collect::<Vec<_>>();
+        flip_edges_to_improve_degree_of_halfedges(mesh_graph, &halfedge_ids);
+
+        let patch_vertices = patch_vertices(mesh_graph, &selection, &original_vertices);
+        tangential_relaxation_of_vertices(mesh_graph, &patch_vertices, 0.5);
+    }
+
+    if mode == HoleFillMode::Fair {
+        let patch_vertices = patch_vertices(mesh_graph, &selection, &original_vertices);
+        fair_vertices(mesh_graph, &patch_vertices);
+    }
+
+    true
+}
+
+/// Same as `MeshGraph::subdivide_until_edges_below_max_length` but only the faces on the patch side of a split
+/// edge are added to the patch.
+///
+/// Edges on the boundary of the hole are split as well, because otherwise the faces next to long boundary edges
+/// can never reach the maximum edge length. The faces around the hole are split with them but keep their shape.
+fn subdivide_patch(mesh_graph: &mut MeshGraph, max_length_squared: f32, patch: &mut Selection) {
+    let mut halfedges_to_subdivide = HashMap::new();
+
+    for he_id in patch.resolve_to_halfedges(mesh_graph) {
+        insert_if_too_long(
+            mesh_graph,
+            patch,
+            &mut halfedges_to_subdivide,
+            he_id,
+            max_length_squared,
+        );
+    }
+
+    while let Some((&max_he_id, _)) = halfedges_to_subdivide
+        .iter()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+    {
+        halfedges_to_subdivide.remove(&max_he_id);
+
+        let Some(&max_he) = mesh_graph
+            .halfedges
+            .get(max_he_id)
+            .or_else(error_none!("Halfedge not found"))
+        else {
+            continue;
+        };
+
+        let in_patch =
+            |face_id: Option<FaceId>| face_id.is_some_and(|face_id| patch.faces.contains(&face_id));
+
+        let he_in_patch = in_patch(max_he.face);
+        let twin_in_patch = in_patch(
+            max_he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face),
+        );
+
+        let new_edges = mesh_graph.subdivide_edge(max_he_id);
+
+        // the first new halfedge is in the face split off from the face of `max_he_id` and
+        // the new twin of `max_he_id` is in the face split off from the face of the original twin
+        let new_faces = [
+            (
+                he_in_patch,
+                new_edges
+                    .first()
+                    .and_then(|&he_id| mesh_graph.halfedges.get(he_id))
+                    .and_then(|he| he.face),
+            ),
+            (
+                twin_in_patch,
+                mesh_graph
+                    .halfedges
+                    .get(max_he_id)
+                    .and_then(|he| he.twin)
+                    .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                    .and_then(|twin| twin.face),
+            ),
+        ];
+
+        let mut affected_faces = Selection::default();
+
+        for (is_patch, face_id) in new_faces {
+            if is_patch && let Some(face_id) = face_id {
+                patch.insert(face_id);
+            }
+        }
+
+        for he_id in new_edges.into_iter().chain([max_he_id]) {
+            let Some(he) = mesh_graph.halfedges.get(he_id) else {
+                error!("Halfedge not found");
+                continue;
+            };
+
+            let twin_face = he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face);
+
+            for face_id in [he.face, twin_face].into_iter().flatten() {
+                if patch.faces.contains(&face_id) {
+                    affected_faces.insert(face_id);
+                }
+            }
+        }
+
+        for he_id in affected_faces.resolve_to_halfedges(mesh_graph) {
+            insert_if_too_long(
+                mesh_graph,
+                patch,
+                &mut halfedges_to_subdivide,
+                he_id,
+                max_length_squared,
+            );
+        }
+    }
+}
+
+/// Adds the halfedge to `halfedges_to_subdivide` if it belongs to a face of the patch, is longer than
+/// the maximum length and its twin isn't in there already.
+fn insert_if_too_long(
+    mesh_graph: &MeshGraph,
+    patch: &Selection,
+    halfedges_to_subdivide: &mut HashMap<HalfedgeId, f32>,
+    he_id: HalfedgeId,
+    max_length_squared: f32,
+) {
+    let Some(he) = mesh_graph.halfedges.get(he_id) else {
+        return;
+    };
+
+    if he
+        .twin
+        .is_some_and(|twin| halfedges_to_subdivide.contains_key(&twin))
+        || !he
+            .face
+            .is_some_and(|face_id| patch.faces.contains(&face_id))
+    {
+        return;
+    }
+
+    let len_sqr = he.length_squared(mesh_graph);
+
+    if len_sqr > max_length_squared {
+        halfedges_to_subdivide.insert(he_id, len_sqr);
+    }
+}
+
+/// Returns `true` if both sides of the edge of the given halfedge are faces of the patch.
+fn is_patch_interior(mesh_graph: &MeshGraph, patch: &Selection, he_id: HalfedgeId) -> bool {
+    let Some(he) = mesh_graph.halfedges.get(he_id) else {
+        return false;
+    };
+
+    let twin_face = he
+        .twin
+        .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+        .and_then(|twin| twin.face);
+
+    [he.face, twin_face]
+        .into_iter()
+        .all(|face_id| face_id.is_some_and(|face_id| patch.faces.contains(&face_id)))
+}
+
+/// The vertices that were created inside of the hole.
+///
+/// The vertices on the boundary of the hole, including the ones that were created by splitting boundary edges,
+/// are excluded so they stay in place.
+fn patch_vertices(
+    mesh_graph: &MeshGraph,
+    selection: &Selection,
+    original_vertices: &HashSet<VertexId>,
+) -> Vec<VertexId> {
+    selection
+        .resolve_to_vertices(mesh_graph)
+        .into_iter()
+        .filter(|v_id| !original_vertices.contains(v_id))
+        .filter(|&v_id| {
+            mesh_graph.vertices.get(v_id).is_some_and(|vertex| {
+                vertex
+                    .faces(mesh_graph)
+                    .all(|face_id| selection.faces.contains(&face_id))
+            })
+        })
+        .collect()
+}
+
+fn is_boundary_halfedge(he: &Halfedge, mesh_graph: &MeshGraph) -> bool {
+    !he.is_boundary()
+        && he
+            .twin
+            .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+            .is_none_or(|twin| twin.is_boundary())
+}
+
+/// Walks along the boundary starting at the given halfedge until it arrives back at it.
+fn boundary_loop(mesh_graph: &MeshGraph, start_he_id: HalfedgeId) -> Option<Vec<HalfedgeId>> {
+    let start_he = mesh_graph
+        .halfedges
+        .get(start_he_id)
+        .or_else(error_none!("Halfedge not found"))?;
+
+    if !is_boundary_halfedge(start_he, mesh_graph) {
+        return None;
+    }
+
+    let mut boundary_loop = vec![start_he_id];
+    let mut he_id = start_he_id;
+
+    loop {
+        he_id = next_boundary_halfedge(mesh_graph, he_id)?;
+
+        if he_id == start_he_id {
+            return Some(boundary_loop);
+        }
+        if boundary_loop.len() > mesh_graph.halfedges.len() {
+            return None;
+        }
+
+        boundary_loop.push(he_id);
+    }
+}
+
+/// Finds the boundary halfedge that starts at the end vertex of the given boundary halfedge
+/// by rotating around that vertex through the faces.
+fn next_boundary_halfedge(mesh_graph: &MeshGraph, he_id: HalfedgeId) -> Option<HalfedgeId> {
+    let mut outgoing_id = mesh_graph.halfedges.get(he_id)?.next?;
+
+    for _ in 0..mesh_graph.halfedges.len() {
+        let outgoing = mesh_graph.halfedges.get(outgoing_id)?;
+
+        if is_boundary_halfedge(outgoing, mesh_graph) {
+            return Some(outgoing_id);
+        }
+
+        outgoing_id = mesh_graph.halfedges.get(outgoing.twin?)?.next?;
+    }
+
+    None
+}
+
+/// Closes the hole with the triangulation of minimal area (dynamic programming over all
+/// triangulations of the boundary polygon) and inserts the new faces into the mesh graph.
+///
+/// Returns the ids of the new faces.
+fn triangulate_hole(
+    mesh_graph: &mut MeshGraph,
+    boundary_loop: &[HalfedgeId],
+) -> Option<Vec<FaceId>> {
+    let n = boundary_loop.len();
+
+    if n < 3 {
+        error!("Hole has less than three edges");
+        return None;
+    }
+
+    // vertices[i] is the start vertex of boundary_loop[i]
+    let mut vertices = boundary_loop
+        .iter()
+        .map(|&he_id| {
+            mesh_graph
+                .halfedges
+                .get(he_id)
+                .map(|he| he.end_vertex)
+                .or_else(error_none!("Halfedge not found"))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    vertices.rotate_right(1);
+
+    let positions = vertices
+        .iter()
+        .map(|&v_id| {
+            mesh_graph
+                .positions
+                .get(v_id)
+                .copied()
+                .or_else(error_none!("Vertex position not found"))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let neighbours = vertices
+        .iter()
+        .map(|&v_id| {
+            let neighbours = mesh_graph
+                .vertices
+                .get(v_id)
+                .map(|v| v.neighbours(mesh_graph).collect::<HashSet<_>>())
+                .unwrap_or_default();
+            (v_id, neighbours)
+        })
+        .collect::<HashMap<_, _>>();
+
+    // new inner edges must not duplicate existing edges of the mesh
+    let can_connect = |i: usize, j: usize| {
+        vertices[i] != vertices[j]
+            && (j - i == 1
+                || (i == 0 && j == n - 1)
+                || !neighbours[&vertices[i]].contains(&vertices[j]))
+    };
+
+    let triangles = minimum_area_triangulation(&positions, can_connect)
+        .or_else(error_none!("Hole can't be triangulated"))?;
+
+    // boundary halfedges keyed by (start, end) so the new halfedges can find their twins
+    let mut twins = HashMap::with_capacity(n + triangles.len() * 3);
+
+    for (i, &he_id) in boundary_loop.iter().enumerate() {
+        // halfedges without face that are left over from deleting faces are replaced by the patch
+        if let Some(twin_id) = mesh_graph.halfedges[he_id].twin {
+            mesh_graph.halfedges.remove(twin_id);
+            mesh_graph.halfedges[he_id].twin = None;
+        }
+
+        twins.insert((vertices[i], vertices[(i + 1) % n]), he_id);
+    }
+
+    let mut new_faces = Vec::with_capacity(triangles.len());
+
+    for [i, m, j] in triangles {
+        // the patch is oriented opposite to the boundary
+        let corners = [vertices[j], vertices[m], vertices[i]];
+
+        let halfedge_ids = [
+            mesh_graph.insert_halfedge(corners[1]),
+            mesh_graph.insert_halfedge(corners[2]),
+            mesh_graph.insert_halfedge(corners[0]),
+        ];
+        let face_id = mesh_graph.insert_face(halfedge_ids[0]);
+
+        for k in 0..3 {
+            let he_id = halfedge_ids[k];
+            let start = corners[k];
+            let end = corners[(k + 1) % 3];
+
+            let he = &mut mesh_graph.halfedges[he_id];
+            he.next = Some(halfedge_ids[(k + 1) % 3]);
+            he.face = Some(face_id);
+
+            if let Some(twin_id) = twins.remove(&(end, start)) {
+                mesh_graph.halfedges[he_id].twin = Some(twin_id);
+                mesh_graph.halfedges[twin_id].twin = Some(he_id);
+            } else {
+                twins.insert((start, end), he_id);
+            }
+
+            mesh_graph.vertices[start].outgoing_halfedge = Some(he_id);
+        }
+
+        let face = mesh_graph.faces[face_id];
+        mesh_graph
+            .bvh
+            .insert_or_update_partially(face.aabb(mesh_graph), face.index, 0.0);
+
+        new_faces.push(face_id);
+    }
+
+    Some(new_faces)
+}
+
+/// Computes the triangulation of the polygon with the least total area.
+///
+/// `can_connect(i, j)` with `i < j` decides if the polygon vertices `i` and `j` may be connected.
+/// Returns the triangles as polygon indices `[i, m, j]` with `i < m < j`.
+fn minimum_area_triangulation(
+    positions: &[Vec3],
+    can_connect: impl Fn(usize, usize) -> bool,
+) -> Option<Vec<[usize; 3]>> {
+    let n = positions.len();
+
+    let mut costs = vec![vec![f32::INFINITY; n]; n];
+    let mut splits = vec![vec![0; n]; n];
+
+    for i in 0..n - 1 {
+        costs[i][i + 1] = 0.0;
+    }
+
+    for len in 2..n {
+        for i in 0..n - len {
+            let j = i + len;
+
+            if !can_connect(i, j) {
+                continue;
+            }
+
+            for m in i + 1..j {
+                if !can_connect(i, m) || !can_connect(m, j) {
+                    continue;
+                }
+
+                let area = (positions[m] - positions[i])
+                    .cross(positions[j] - positions[i])
+                    .length()
+                    * 0.5;
+                let cost = costs[i][m] + costs[m][j] + area;
+
+                if cost < costs[i][j] {
+                    costs[i][j] = cost;
+                    splits[i][j] = m;
+                }
+            }
+        }
+    }
+
+    if !costs[0][n - 1].is_finite() {
+        return None;
+    }
+
+    let mut triangles = Vec::with_capacity(n - 2);
+    let mut stack = vec![(0, n - 1)];
+
+    while let Some((i, j)) = stack.pop() {
+        if j - i < 2 {
+            continue;
+        }
+
+        let m = splits[i][j];
+        triangles.push([i, m, j]);
+        stack.push((i, m));
+        stack.push((m, j));
+    }
+
+    Some(triangles)
+}
+
+/// Smooths the given vertices by minimizing the discrete thin plate energy, i.e. by solving
+/// `Δ²x = 0` with the uniform graph Laplacian `Δ` as described by
+/// [Kobbelt et al.](https://www.graphics.rwth-aachen.de/media/papers/p_Kobbelt_SIGGRAPH98.pdf).
+/// All other vertices stay fixed, so the smoothed region blends into its surroundings with
+/// continuous tangents.
+///
+/// The linear system is solved with conjugate gradients without building the matrix.
+fn fair_vertices(mesh_graph: &mut MeshGraph, vertex_ids: &[VertexId]) {
+    let free = vertex_ids
+        .iter()
+        .enumerate()
+        .map(|(i, &v_id)| (v_id, i))
+        .collect::<HashMap<_, _>>();
+
+    // the Laplacian is evaluated for the free vertices and their neighbours
+    let mut neighbourhood = HashMap::<VertexId, Vec<VertexId>>::new();
+
+    for &vertex_id in vertex_ids {
+        let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+            error!("Vertex not found");
+            return;
+        };
+
+        let neighbours = vertex.neighbours(mesh_graph).collect::<Vec<_>>();
+
+        for &neighbour_id in &neighbours {
+            if !neighbourhood.contains_key(&neighbour_id)
+                && let Some(neighbour) = mesh_graph.vertices.get(neighbour_id)
+            {
+                neighbourhood.insert(neighbour_id, neighbour.neighbours(mesh_graph).collect());
+            }
+        }
+
+        neighbourhood.insert(vertex_id, neighbours);
+    }
+
+    let laplacian = |vertex_id: VertexId, value: &dyn Fn(VertexId) -> DVec3| {
+        let neighbours = &neighbourhood[&vertex_id];
+        value(vertex_id) * neighbours.len() as f64
+            - neighbours.iter().map(|&n_id| value(n_id)).sum::<DVec3>()
+    };
+
+    // applies the bi-Laplacian to the values of the free vertices with all other values given by `fixed`
+    let bi_laplacian = |values: &[DVec3], fixed: &dyn Fn(VertexId) -> DVec3| {
+        let value = |v_id: VertexId| free.get(&v_id).map_or_else(|| fixed(v_id), |&i| values[i]);
+
+        let laplacians = neighbourhood
+            .keys()
+            .map(|&v_id| (v_id, laplacian(v_id, &value)))
+            .collect::<HashMap<_, _>>();
+
+        vertex_ids
+            .iter()
+            .map(|&v_id| laplacian(v_id, &|n_id| laplacians[&n_id]))
+            .collect::<Vec<_>>()
+    };
+
+    let fixed_position = |v_id: VertexId| {
+        mesh_graph
+            .positions
+            .get(v_id)
+            .map(|pos| pos.as_dvec3())
+            .unwrap_or_default()
+    };
+
+    let dot = |a: &[DVec3], b: &[DVec3]| a.iter().zip(b).map(|(a, b)| a.dot(*b)).sum::<f64>();
+
+    let mut positions = vertex_ids
+        .iter()
+        .map(|&v_id| fixed_position(v_id))
+        .collect::<Vec<_>>();
+
+    // right hand side: the influence of the fixed vertices
+    let rhs = bi_laplacian(&vec![DVec3::ZERO; vertex_ids.len()], &fixed_position)
+        .into_iter()
+        .map(|v| -v)
+        .collect::<Vec<_>>();
+
+    let mut residual = bi_laplacian(&positions, &|_| DVec3::ZERO)
+        .into_iter()
+        .zip(&rhs)
+        .map(|(applied, rhs)| rhs - applied)
+        .collect::<Vec<_>>();
+    let mut direction = residual.clone();
+    let mut residual_sq = dot(&residual, &residual);
+
+    let tolerance_sq = dot(&rhs, &rhs) * FAIRING_TOLERANCE * FAIRING_TOLERANCE;
+
+    for _ in 0..MAX_FAIRING_ITERATIONS {
+        if residual_sq <= tolerance_sq {
+            break;
+        }
+
+        let applied = bi_laplacian(&direction, &|_| DVec3::ZERO);
+        let step = residual_sq / dot(&direction, &applied);
+
+        if !step.is_finite() {
+            break;
+        }
+
+        for i in 0..positions.len() {
+            positions[i] += direction[i] * step;
+            residual[i] -= applied[i] * step;
+        }
+
+        let new_residual_sq = dot(&residual, &residual);
+        let beta = new_residual_sq / residual_sq;
+        residual_sq = new_residual_sq;
+
+        for i in 0..direction.len() {
+            direction[i] = residual[i] + direction[i] * beta;
+        }
+    }
+
+    for (&vertex_id, pos) in vertex_ids.iter().zip(positions) {
+        mesh_graph.positions[vertex_id] = pos.as_vec3();
+    }
+}
@@ -0,0 +1,61 @@
+use glam::vec3;
+use mesh_graph::MeshGraph;
+use parry3d::{math::Point, query::PointQuery};
+use tracing::{error, instrument};
+
+use crate::utils::rebuild_bvh_from_scratch;
+
+use super::remesh_iteration;
+
+/// Remeshes the whole mesh graph into (mostly) equilateral triangles with edges of about `target_edge_length`.
+///
+/// This implements the isotropic remeshing algorithm of
+/// [Botsch and Kobbelt](https://www.graphics.rwth-aachen.de/media/papers/remeshing1.pdf).
+/// In each of the `iterations` all edges longer than 4/3 of the target length are split,
+/// all edges shorter than 4/5 of the target length are collapsed, edges are flipped to even out
+/// vertex degrees and the vertices are relaxed tangentially. After every iteration the vertices are
+/// projected back onto the original surface so the shape of the mesh is preserved.
+///
+/// This is useful to clean up scan data or other irregular meshes before sculpting on them.
+#[instrument(skip(mesh_graph))]
+pub fn isotropic_remesh(mesh_graph: &mut MeshGraph, target_edge_length: f32, iterations: usize) {
+    if target_edge_length.is_nan() || target_edge_length <= 0.0 {
+        error!("Target edge length has to be positive");
+        return;
+    }
+
+    let mut original = mesh_graph.clone();
+    rebuild_bvh_from_scratch(&mut original);
+
+    let max_edge_length = target_edge_length * 4.0 / 3.0;
+    let min_edge_length = target_edge_length * 4.0 / 5.0;
+
+    for _ in 0..iterations {
+        remesh_iteration(
+            mesh_graph,
+            min_edge_length * min_edge_length,
+            max_edge_length * max_edge_length,
+        );
+
+        project_onto(mesh_graph, &original);
+    }
+
+    if mesh_graph.vertex_normals.is_some() {
+        mesh_graph.compute_vertex_normals();
+    }
+    rebuild_bvh_from_scratch(mesh_graph);
+}
+
+/// Moves every vertex of `mesh_graph` to the closest point on the surface of `target`.
+fn project_onto(mesh_graph: &mut MeshGraph, target: &MeshGraph) {
+    if target.faces.is_empty() {
+        error!("Target mesh has no faces");
+        return;
+    }
+
+    for pos in mesh_graph.positions.values_mut() {
+        let projection = target.project_local_point(&Point::new(pos.x, pos.y, pos.z), false);
+
+        *pos = vec3(projection.point.x, projection.point.y, projection.point.z);
+    }
+}
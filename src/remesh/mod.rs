@@ -0,0 +1,34 @@
+mod decimate;
+mod flip;
+mod flood_fill;
+mod hole_filling;
+mod isotropic;
+mod relax;
+mod voxel;
+
+pub use decimate::*;
+pub use flip::*;
+pub use flood_fill::*;
+pub use hole_filling::*;
+pub use isotropic::*;
+pub use relax::*;
+pub use voxel::*;
+
+use mesh_graph::{MeshGraph, Selection};
+
+/// One round of remeshing: split all edges above the maximum length, collapse all edges
+/// below the minimum length, flip edges to even out vertex degrees and relax tangentially.
+fn remesh_iteration(
+    mesh_graph: &mut MeshGraph,
+    min_edge_length_squared: f32,
+    max_edge_length_squared: f32,
+) {
+    let mut selection = Selection::select_all(mesh_graph);
+    mesh_graph.subdivide_until_edges_below_max_length(max_edge_length_squared, &mut selection);
+
+    let mut selection = Selection::select_all(mesh_graph);
+    mesh_graph.collapse_until_edges_above_min_length(min_edge_length_squared, &mut selection);
+
+    flip_edges_to_improve_degree(mesh_graph);
+    tangential_relaxation(mesh_graph, 0.5);
+}
@@ -0,0 +1,71 @@
+use glam::Vec3;
+use mesh_graph::{MeshGraph, VertexId};
+use tracing::{error, instrument};
+
+use crate::utils::{is_boundary_vertex, vertex_normal};
+
+/// Moves every inner vertex towards the average of its neighbours, but only tangentially
+/// to the surface, i.e. the movement along the vertex normal is removed.
+///
+/// This evens out the distribution of vertices without changing the shape of the mesh much.
+/// Boundary vertices are not moved.
+///
+/// `factor` controls how far the vertices are moved towards the average, `1.0` moves them all the way.
+#[instrument(skip(mesh_graph))]
+pub fn tangential_relaxation(mesh_graph: &mut MeshGraph, factor: f32) {
+    let vertex_ids = mesh_graph.vertices.keys().collect::<Vec<_>>();
+    tangential_relaxation_of_vertices(mesh_graph, &vertex_ids, factor);
+}
+
+/// Same as [`tangential_relaxation`] but only for the given vertices.
+#[instrument(skip(mesh_graph, vertex_ids))]
+pub fn tangential_relaxation_of_vertices(
+    mesh_graph: &mut MeshGraph,
+    vertex_ids: &[VertexId],
+    factor: f32,
+) {
+    let mut new_positions = Vec::with_capacity(vertex_ids.len());
+
+    for &vertex_id in vertex_ids {
+        if is_boundary_vertex(vertex_id, mesh_graph) {
+            continue;
+        }
+
+        let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+            error!("Vertex not found");
+            continue;
+        };
+        let Some(&pos) = mesh_graph.positions.get(vertex_id) else {
+            error!("Vertex position not found");
+            continue;
+        };
+        let Some(normal) = vertex_normal(vertex_id, mesh_graph) else {
+            continue;
+        };
+
+        let mut center = Vec3::ZERO;
+        let mut count = 0;
+
+        for neighbour in vertex.neighbours(mesh_graph) {
+            if let Some(neighbour_pos) = mesh_graph.positions.get(neighbour) {
+                center += neighbour_pos;
+                count += 1;
+            } else {
+                error!("Neighbour position not found");
+            }
+        }
+
+        if count == 0 {
+            continue;
+        }
+
+        let diff = center / count as f32 - pos;
+        let tangential_diff = diff - normal * normal.dot(diff);
+
+        new_positions.push((vertex_id, pos + tangential_diff * factor));
+    }
+
+    for (vertex_id, pos) in new_positions {
+        mesh_graph.positions[vertex_id] = pos;
+    }
+}
@@ -0,0 +1,359 @@
+use glam::{IVec3, Vec3, ivec3};
+use hashbrown::HashMap;
+use mesh_graph::MeshGraph;
+use parry3d::{math::Point, query::PointQueryWithLocation};
+use tracing::{error, instrument};
+
+use crate::utils::rebuild_bvh_from_scratch;
+
+/// The offsets of the 8 corners of a voxel cube.
+const CUBE_CORNERS: [IVec3; 8] = [
+    ivec3(0, 0, 0),
+    ivec3(1, 0, 0),
+    ivec3(1, 1, 0),
+    ivec3(0, 1, 0),
+    ivec3(0, 0, 1),
+    ivec3(1, 0, 1),
+    ivec3(1, 1, 1),
+    ivec3(0, 1, 1),
+];
+
+/// Splits a cube into 6 tetrahedra that all share the diagonal from corner 0 to corner 6.
+/// This split is the same for all cubes so the tetrahedra of neighbouring cubes fit together.
+const CUBE_TETRAHEDRA: [[usize; 4]; 6] = [
+    [0, 1, 2, 6],
+    [0, 1, 5, 6],
+    [0, 3, 2, 6],
+    [0, 3, 7, 6],
+    [0, 4, 5, 6],
+    [0, 4, 7, 6],
+];
+
+/// Rebuilds the mesh graph from a signed distance field of the current mesh.
+///
+/// The mesh is rasterized into a grid with cells of size `voxel_size` and a new surface is
+/// extracted from the grid with marching tetrahedra (a variant of marching cubes that doesn't
+/// suffer from ambiguous cases). The result is always a closed manifold mesh with about
+/// `voxel_size` long edges.
+///
+/// This is the standard way to fix messy topology. As overlapping parts of the mesh are merged
+/// into one volume it also allows for changes of the topology genus which dynamic topology can't do.
+///
+/// The mesh should be closed and consistently oriented because the inside is determined by the winding number.
+/// The resulting triangles are not very regular, so it is a good idea to call
+/// [`detail_flood_fill`](super::detail_flood_fill) or [`isotropic_remesh`](super::isotropic_remesh) afterwards.
+#[instrument(skip(mesh_graph))]
+pub fn voxel_remesh(mesh_graph: &mut MeshGraph, voxel_size: f32) {
+    if voxel_size <= 0.0 {
+        error!("Voxel size has to be positive");
+        return;
+    }
+
+    let Some((min, max)) = bounds(mesh_graph) else {
+        error!("Mesh has no vertices");
+        return;
+    };
+
+    let grid = SdfGrid::new(mesh_graph, min, max, voxel_size);
+    let (positions, indices) = grid.marching_tetrahedra();
+
+    if indices.is_empty() {
+        error!("Voxel remesh produced no faces. Is the voxel size too large?");
+        return;
+    }
+
+    let had_normals = mesh_graph.vertex_normals.is_some();
+
+    *mesh_graph = MeshGraph::indexed_triangles(&positions, &indices);
+    rebuild_bvh_from_scratch(mesh_graph);
+
+    if had_normals {
+        mesh_graph.compute_vertex_normals();
+    }
+}
+
+fn bounds(mesh_graph: &MeshGraph) -> Option<(Vec3, Vec3)> {
+    mesh_graph
+        .positions
+        .values()
+        .fold(None, |acc, &pos| match acc {
+            Some((min, max)) => Some((pos.min(min), pos.max(max))),
+            None => Some((pos, pos)),
+        })
+}
+
+/// Grid of signed distances. Negative values are inside of the mesh.
+struct SdfGrid {
+    origin: Vec3,
+    voxel_size: f32,
+    size: IVec3,
+    values: Vec<f32>,
+}
+
+impl SdfGrid {
+    fn new(mesh_graph: &MeshGraph, min: Vec3, max: Vec3, voxel_size: f32) -> Self {
+        // pad by two voxels so the border of the grid is guaranteed to be outside
+        let origin = min - Vec3::splat(voxel_size * 2.0);
+        let size = ((max - origin) / voxel_size).ceil().as_ivec3() + IVec3::splat(3);
+
+        let mut grid = Self {
+            origin,
+            voxel_size,
+            size,
+            values: vec![0.0; (size.x * size.y * size.z) as usize],
+        };
+
+        let inside = grid.compute_inside(mesh_graph);
+
+        // distances further away than this don't matter for the surface extraction
+        let band = voxel_size * 2.0;
+
+        for z in 0..size.z {
+            for y in 0..size.y {
+                for x in 0..size.x {
+                    let coords = ivec3(x, y, z);
+                    let pos = grid.position(coords);
+                    let idx = grid.index(coords);
+
+                    let distance = mesh_graph
+                        .project_local_point_and_get_location_with_max_dist(
+                            &Point::new(pos.x, pos.y, pos.z),
+                            false,
+                            band,
+                        )
+                        .map(|(projection, _)| {
+                            pos.distance(Vec3::new(
+                                projection.point.x,
+                                projection.point.y,
+                                projection.point.z,
+                            ))
+                        })
+                        .unwrap_or(band)
+                        // exact zeros would create degenerate triangles
+                        .max(voxel_size * 1e-4);
+
+                    grid.values[idx] = if inside[idx] { -distance } else { distance };
+                }
+            }
+        }
+
+        grid
+    }
+
+    #[inline]
+    fn index(&self, coords: IVec3) -> usize {
+        (coords.x + self.size.x * (coords.y + self.size.y * coords.z)) as usize
+    }
+
+    #[inline]
+    fn position(&self, coords: IVec3) -> Vec3 {
+        self.origin + coords.as_vec3() * self.voxel_size
+    }
+
+    /// Determines for every grid point if it is inside of the mesh by casting rays along the x axis
+    /// and computing the winding number from the triangles that are crossed before reaching the point.
+    /// Using the winding number instead of just counting crossings makes overlapping parts count as inside.
+    fn compute_inside(&self, mesh_graph: &MeshGraph) -> Vec<bool> {
+        // slightly shift the rays so they don't hit edges or vertices exactly
+        let jitter_y = self.voxel_size * 1.234e-4;
+        let jitter_z = self.voxel_size * 2.345e-4;
+
+        let row_index = |y: i32, z: i32| (y + self.size.y * z) as usize;
+        let mut crossings = vec![Vec::<(f32, i32)>::new(); (self.size.y * self.size.z) as usize];
+
+        for face in mesh_graph.faces.values() {
+            let corners = face
+                .vertices(mesh_graph)
+                .filter_map(|v_id| mesh_graph.positions.get(v_id).copied())
+                .collect::<Vec<_>>();
+
+            let [a, b, c] = corners[..] else {
+                error!("Face doesn't have three vertex positions");
+                continue;
+            };
+
+            let ab = b - a;
+            let ac = c - a;
+            let det = ab.y * ac.z - ab.z * ac.y;
+
+            if det.abs() < f32::EPSILON {
+                // parallel to the rays
+                continue;
+            }
+
+            let tri_min = a.min(b).min(c);
+            let tri_max = a.max(b).max(c);
+
+            let y_start =
+                (((tri_min.y - self.origin.y - jitter_y) / self.voxel_size).ceil() as i32).max(0);
+            let y_end = (((tri_max.y - self.origin.y - jitter_y) / self.voxel_size).floor() as i32)
+                .min(self.size.y - 1);
+            let z_start =
+                (((tri_min.z - self.origin.z - jitter_z) / self.voxel_size).ceil() as i32).max(0);
+            let z_end = (((tri_max.z - self.origin.z - jitter_z) / self.voxel_size).floor() as i32)
+                .min(self.size.z - 1);
+
+            for z in z_start..=z_end {
+                for y in y_start..=y_end {
+                    let ray_y = self.origin.y + y as f32 * self.voxel_size + jitter_y;
+                    let ray_z = self.origin.z + z as f32 * self.voxel_size + jitter_z;
+
+                    let dy = ray_y - a.y;
+                    let dz = ray_z - a.z;
+
+                    // barycentric coordinates in the yz plane
+                    let u = (dy * ac.z - dz * ac.y) / det;
+                    let v = (ab.y * dz - ab.z * dy) / det;
+
+                    if u >= 0.0 && v >= 0.0 && u + v <= 1.0 {
+                        // `det` is the x component of the face normal. The ray enters the mesh
+                        // through faces that point against the ray direction.
+                        let winding = if det < 0.0 { 1 } else { -1 };
+
+                        crossings[row_index(y, z)].push((a.x + u * ab.x + v * ac.x, winding));
+                    }
+                }
+            }
+        }
+
+        let mut inside = vec![false; self.values.len()];
+
+        for z in 0..self.size.z {
+            for y in 0..self.size.y {
+                let row = &mut crossings[row_index(y, z)];
+                row.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+                let mut crossed = 0;
+                let mut winding_number = 0;
+
+                for x in 0..self.size.x {
+                    let pos_x = self.origin.x + x as f32 * self.voxel_size;
+
+                    while crossed < row.len() && row[crossed].0 < pos_x {
+                        winding_number += row[crossed].1;
+                        crossed += 1;
+                    }
+
+                    inside[self.index(ivec3(x, y, z))] = winding_number > 0;
+                }
+            }
+        }
+
+        inside
+    }
+
+    /// Extracts the zero level set of the grid as a triangle mesh.
+    fn marching_tetrahedra(&self) -> (Vec<Vec3>, Vec<usize>) {
+        let mut positions = Vec::new();
+        let mut indices = Vec::new();
+
+        // every vertex lies on an edge between two grid points
+        let mut edge_to_vertex = HashMap::<(usize, usize), usize>::new();
+
+        let mut edge_vertex = |a: IVec3, b: IVec3, positions: &mut Vec<Vec3>| {
+            let idx_a = self.index(a);
+            let idx_b = self.index(b);
+            let key = (idx_a.min(idx_b), idx_a.max(idx_b));
+
+            *edge_to_vertex.entry(key).or_insert_with(|| {
+                let value_a = self.values[idx_a];
+                let value_b = self.values[idx_b];
+                let t = value_a / (value_a - value_b);
+
+                positions.push(self.position(a).lerp(self.position(b), t));
+                positions.len() - 1
+            })
+        };
+
+        for z in 0..self.size.z - 1 {
+            for y in 0..self.size.y - 1 {
+                for x in 0..self.size.x - 1 {
+                    let cube = ivec3(x, y, z);
+
+                    for tetrahedron in CUBE_TETRAHEDRA {
+                        let corners = tetrahedron.map(|corner| cube + CUBE_CORNERS[corner]);
+                        let values = corners.map(|corner| self.values[self.index(corner)]);
+
+                        let (inside, outside): (Vec<usize>, Vec<usize>) =
+                            (0..4).partition(|&i| values[i] < 0.0);
+
+                        let triangles = match (inside.len(), outside.len()) {
+                            (1, 3) | (3, 1) => {
+                                let (single, others) = if inside.len() == 1 {
+                                    (inside[0], &outside)
+                                } else {
+                                    (outside[0], &inside)
+                                };
+
+                                vec![[
+                                    edge_vertex(
+                                        corners[single],
+                                        corners[others[0]],
+                                        &mut positions,
+                                    ),
+                                    edge_vertex(
+                                        corners[single],
+                                        corners[others[1]],
+                                        &mut positions,
+                                    ),
+                                    edge_vertex(
+                                        corners[single],
+                                        corners[others[2]],
+                                        &mut positions,
+                                    ),
+                                ]]
+                            }
+                            (2, 2) => {
+                                let a = edge_vertex(
+                                    corners[inside[0]],
+                                    corners[outside[0]],
+                                    &mut positions,
+                                );
+                                let b = edge_vertex(
+                                    corners[inside[0]],
+                                    corners[outside[1]],
+                                    &mut positions,
+                                );
+                                let c = edge_vertex(
+                                    corners[inside[1]],
+                                    corners[outside[1]],
+                                    &mut positions,
+                                );
+                                let d = edge_vertex(
+                                    corners[inside[1]],
+                                    corners[outside[0]],
+                                    &mut positions,
+                                );
+
+                                vec![[a, b, c], [a, c, d]]
+                            }
+                            _ => continue,
+                        };
+
+                        let inside_center =
+                            inside.iter().map(|&i| corners[i].as_vec3()).sum::<Vec3>()
+                                / inside.len() as f32;
+                        let outside_center =
+                            outside.iter().map(|&i| corners[i].as_vec3()).sum::<Vec3>()
+                                / outside.len() as f32;
+                        let outwards = outside_center - inside_center;
+
+                        for [a, b, c] in triangles {
+                            let normal =
+                                (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+
+                            // make sure all triangles face outwards
+                            if normal.dot(outwards) < 0.0 {
+                                indices.extend([a, c, b]);
+                            } else {
+                                indices.extend([a, b, c]);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        (positions, indices)
+    }
+}
@@ -0,0 +1,317 @@
+use glam::{Quat, Vec2, Vec3};
+use mesh_graph::MeshGraph;
+use tracing::{error, instrument};
+
+use crate::{ray::FaceIntersection, utils::face_area_normal};
+
+use super::{MeshSelector, SelectionInput, WeightedSelection, map_vertex_weights};
+
+/// A grayscale image with values from 0.0 (no influence) to 1.0 (full influence) used by [`AlphaStamp`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlphaTexture {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+impl AlphaTexture {
+    /// Creates a new texture from row-major values starting at the top left pixel.
+    ///
+    /// Returns `None` if the number of values doesn't match the size.
+    pub fn new(width: usize, height: usize, values: Vec<f32>) -> Option<Self> {
+        if values.len() != width * height {
+            error!(
+                "Expected {} values for a {width}x{height} texture but got {}",
+                width * height,
+                values.len()
+            );
+            return None;
+        }
+
+        Some(Self {
+            width,
+            height,
+            values,
+        })
+    }
+
+    /// Creates a new texture from row-major 8 bit values, e.g. the red channel of an image.
+    pub fn from_luma8(width: usize, height: usize, values: &[u8]) -> Option<Self> {
+        Self::new(
+            width,
+            height,
+            values.iter().map(|&value| value as f32 / 255.0).collect(),
+        )
+    }
+
+    /// Creates a new texture by evaluating `f` at the uv coordinates of the pixel centers.
+    ///
+    /// `(0, 0)` is the top left corner and `(1, 1)` the bottom right corner of the texture.
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(Vec2) -> f32) -> Self {
+        let size = Vec2::new(width as f32, height as f32);
+
+        let values = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Vec2::new(x as f32, y as f32)))
+            .map(|pixel| f((pixel + 0.5) / size))
+            .collect();
+
+        Self {
+            width,
+            height,
+            values,
+        }
+    }
+
+    /// The number of pixels per row.
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The number of rows.
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Samples the texture with bilinear filtering. Outside of the texture the value is 0.0.
+    ///
+    /// `(0, 0)` is the top left corner and `(1, 1)` the bottom right corner of the texture.
+    pub fn sample(&self, uv: Vec2) -> f32 {
+        if self.values.is_empty() || !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return 0.0;
+        }
+
+        let pixel = uv * Vec2::new(self.width as f32, self.height as f32) - 0.5;
+        let base = pixel.floor();
+        let fraction = pixel - base;
+
+        let value = |x: f32, y: f32| {
+            let x = (x as isize).clamp(0, self.width as isize - 1) as usize;
+            let y = (y as isize).clamp(0, self.height as isize - 1) as usize;
+            self.values[y * self.width + x]
+        };
+
+        let top =
+            value(base.x, base.y) * (1.0 - fraction.x) + value(base.x + 1.0, base.y) * fraction.x;
+        let bottom = value(base.x, base.y + 1.0) * (1.0 - fraction.x)
+            + value(base.x + 1.0, base.y + 1.0) * fraction.x;
+
+        (top * (1.0 - fraction.y) + bottom * fraction.y).clamp(0.0, 1.0)
+    }
+}
+
+/// Wraps another selector and multiplies its weights by an [`AlphaTexture`] that is projected onto the surface.
+///
+/// The texture lies in the tangent plane of the picked face, centered at the picked point. This makes
+/// stamp and stencil brushes possible, e.g. to press a pattern into the surface with
+/// [`TranslateDeformation`](crate::deformation::TranslateDeformation).
+///
+/// The weights are converted to [`SelectionWeights::PerVertex`](super::SelectionWeights::PerVertex) so
+/// they don't change while deforming.
+#[derive(Debug, Clone)]
+pub struct AlphaStamp<S: MeshSelector> {
+    /// The wrapped selector.
+    pub selector: S,
+
+    /// The alpha texture.
+    pub texture: AlphaTexture,
+
+    /// Half the width of the projected texture. The texture covers a square of twice this size
+    /// around the picked point before `scale` is applied.
+    pub radius: f32,
+
+    /// Rotation of the texture around the normal of the picked face in radians.
+    pub rotation: f32,
+
+    /// Scales the projected texture along its width and height.
+    pub scale: Vec2,
+
+    /// The direction that the top of the texture points to. It is projected onto the tangent plane.
+    /// If it is parallel to the normal, an arbitrary direction is used.
+    pub up: Vec3,
+}
+
+impl<S: MeshSelector> AlphaStamp<S> {
+    /// Creates a new `AlphaStamp` selector with the top of the texture pointing along the y axis.
+    #[inline]
+    pub fn new(selector: S, texture: AlphaTexture, radius: f32) -> Self {
+        Self {
+            selector,
+            texture,
+            radius,
+            rotation: 0.0,
+            scale: Vec2::ONE,
+            up: Vec3::Y,
+        }
+    }
+
+    /// Returns the texture coordinates of `pos` for a stamp at `center` on a surface with the given normal.
+    pub fn uv(&self, center: Vec3, normal: Vec3, pos: Vec3) -> Vec2 {
+        let up = (self.up - normal * self.up.dot(normal))
+            .try_normalize()
+            .unwrap_or_else(|| normal.any_orthonormal_vector());
+        let up = Quat::from_axis_angle(normal, self.rotation) * up;
+        let right = up.cross(normal);
+
+        let offset = pos - center;
+        let size = self.radius * self.scale;
+
+        let local = Vec2::new(offset.dot(right) / size.x, offset.dot(up) / size.y);
+
+        Vec2::new(0.5 + local.x * 0.5, 0.5 - local.y * 0.5)
+    }
+}
+
+impl<S: MeshSelector> MeshSelector for AlphaStamp<S> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            point: input_pos,
+            face: input_face,
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
+        let Some(normal) =
+            face_area_normal(input_face.id, mesh_graph).and_then(|normal| normal.try_normalize())
+        else {
+            return WeightedSelection::default();
+        };
+
+        if self.radius <= 0.0 || self.scale.min_element() <= 0.0 {
+            return WeightedSelection::default();
+        }
+
+        map_vertex_weights(
+            mesh_graph,
+            self.selector.select(mesh_graph, input),
+            |_, pos, weight| weight * self.texture.sample(self.uv(input_pos, normal, pos)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{vec2, vec3};
+    use mesh_graph::primitives::IcoSphere;
+
+    use crate::{
+        ray::Ray,
+        selectors::{FalloffCurve, MetricWithFalloff},
+    };
+
+    use super::*;
+
+    /// Left column is 0.0, right column is 1.0.
+    fn gradient_texture() -> AlphaTexture {
+        AlphaTexture::new(2, 2, vec![0.0, 1.0, 0.0, 1.0]).unwrap()
+    }
+
+    fn sphere_and_hit() -> (MeshGraph, FaceIntersection) {
+        let mesh_graph = MeshGraph::from(IcoSphere {
+            radius: 1.0,
+            subdivisions: 3,
+        });
+
+        let hit = Ray {
+            origin: vec3(0.0, 5.0, 0.0),
+            direction: vec3(0.0, -1.0, 0.0),
+        }
+        .cast_ray_and_get_face_id(&mesh_graph)
+        .unwrap();
+
+        (mesh_graph, hit)
+    }
+
+    #[test]
+    fn new_rejects_wrong_size() {
+        assert!(AlphaTexture::new(2, 2, vec![0.0; 3]).is_none());
+        assert!(AlphaTexture::from_luma8(2, 1, &[0, 255]).is_some());
+    }
+
+    #[test]
+    fn sample_is_bilinear() {
+        let texture = gradient_texture();
+
+        // pixel centers
+        assert_eq!(texture.sample(vec2(0.25, 0.25)), 0.0);
+        assert_eq!(texture.sample(vec2(0.75, 0.75)), 1.0);
+
+        // between the pixel centers
+        assert!((texture.sample(vec2(0.5, 0.5)) - 0.5).abs() < 1e-6);
+        assert!((texture.sample(vec2(0.375, 0.25)) - 0.25).abs() < 1e-6);
+
+        // clamped to the border pixels towards the edges
+        assert_eq!(texture.sample(vec2(0.0, 0.0)), 0.0);
+        assert_eq!(texture.sample(vec2(1.0, 1.0)), 1.0);
+    }
+
+    #[test]
+    fn sample_outside_is_zero() {
+        let texture = AlphaTexture::from_fn(4, 4, |_| 1.0);
+
+        assert_eq!(texture.sample(vec2(0.5, 0.5)), 1.0);
+        assert_eq!(texture.sample(vec2(-0.01, 0.5)), 0.0);
+        assert_eq!(texture.sample(vec2(0.5, 1.01)), 0.0);
+        assert_eq!(AlphaTexture::default().sample(vec2(0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn stamp_scales_weights() {
+        let (mesh_graph, hit) = sphere_and_hit();
+
+        let selector = MetricWithFalloff::sphere(0.4, 0.2, FalloffCurve::Linear);
+        let stamp = AlphaStamp::new(selector.clone(), AlphaTexture::from_fn(8, 8, |_| 0.5), 10.0);
+
+        let base = selector.select(&mesh_graph, &hit.into());
+        let stamped = stamp.select(&mesh_graph, &hit.into());
+
+        let vertices = stamped.selection.resolve_to_vertices(&mesh_graph);
+        assert!(!vertices.is_empty());
+
+        for vertex_id in vertices {
+            let pos = mesh_graph.positions[vertex_id];
+            let expected = base.weights.weight(vertex_id, pos) * 0.5;
+
+            assert!((stamped.weights.weight(vertex_id, pos) - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn stamp_follows_texture_orientation() {
+        let (mesh_graph, hit) = sphere_and_hit();
+
+        let selector = MetricWithFalloff::sphere(0.4, 0.0, FalloffCurve::Linear);
+        let left_half = AlphaTexture::from_fn(16, 16, |uv| if uv.x < 0.5 { 1.0 } else { 0.0 });
+
+        // the top of the texture points to -z, so its right side is +x
+        let mut stamp = AlphaStamp::new(selector, left_half, 0.4);
+        stamp.up = vec3(0.0, 0.0, -1.0);
+
+        let selected_x = |stamp: &dyn MeshSelector| {
+            let WeightedSelection { selection, weights } = stamp.select(&mesh_graph, &hit.into());
+
+            selection
+                .resolve_to_vertices(&mesh_graph)
+                .into_iter()
+                .filter_map(|vertex_id| {
+                    let pos = mesh_graph.positions[vertex_id];
+                    (weights.weight(vertex_id, pos) > 0.0).then_some(pos.x)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let left = selected_x(&stamp);
+        assert!(!left.is_empty());
+        assert!(left.iter().all(|&x| x <= 1e-4));
+
+        stamp.rotation = std::f32::consts::PI;
+
+        let right = selected_x(&stamp);
+        assert!(!right.is_empty());
+        assert!(right.iter().all(|&x| x >= -1e-4));
+    }
+}
@@ -0,0 +1,212 @@
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
+use tracing::{instrument, warn};
+
+use crate::{curvature::cavity, utils::is_boundary_vertex};
+
+use super::{
+    MeshSelector, SelectionInput, SelectionWeights, WeightedSelection, faces_incident_to_vertices,
+    geodesic_distances,
+};
+
+/// Wraps another selector and automatically attenuates its weights, so less has to be masked by hand.
+///
+/// All enabled modes are combined. The weights are converted to
+/// [`SelectionWeights::PerVertex`] so they don't change while deforming.
+#[derive(Debug, Clone, Copy)]
+pub struct AutoMask<S: MeshSelector> {
+    /// The wrapped selector.
+    pub selector: S,
+
+    /// Only keeps the vertices that are connected to the picked face within the selection.
+    /// Other parts of the mesh that happen to be within the brush are not affected.
+    pub topology: bool,
+
+    /// Only keeps the vertices of the face set of the picked face. This requires [`SelectionInput::attributes`]
+    /// and is skipped without them.
+    pub face_set: bool,
+
+    /// How much cavities are protected. `0.0` disables cavity masking. With larger values
+    /// even shallow cavities are fully masked.
+    pub cavity_factor: f32,
+
+    /// Protects the peaks instead of the cavities.
+    pub invert_cavity: bool,
+
+    /// The geodesic distance from the boundary of an open mesh in which the weights increase from zero to full.
+    /// `0.0` disables boundary masking.
+    pub boundary_distance: f32,
+}
+
+impl<S: MeshSelector> AutoMask<S> {
+    /// Creates a new `AutoMask` selector with all modes disabled.
+    #[inline]
+    pub fn new(selector: S) -> Self {
+        Self {
+            selector,
+            topology: false,
+            face_set: false,
+            cavity_factor: 0.0,
+            invert_cavity: false,
+            boundary_distance: 0.0,
+        }
+    }
+
+    /// Enables topology masking.
+    #[inline]
+    pub fn with_topology(mut self) -> Self {
+        self.topology = true;
+        self
+    }
+
+    /// Enables face set masking.
+    #[inline]
+    pub fn with_face_set(mut self) -> Self {
+        self.face_set = true;
+        self
+    }
+
+    /// Enables cavity masking with the given factor.
+    #[inline]
+    pub fn with_cavity(mut self, cavity_factor: f32, invert: bool) -> Self {
+        self.cavity_factor = cavity_factor;
+        self.invert_cavity = invert;
+        self
+    }
+
+    /// Enables boundary masking with the given distance.
+    #[inline]
+    pub fn with_boundary(mut self, boundary_distance: f32) -> Self {
+        self.boundary_distance = boundary_distance;
+        self
+    }
+}
+
+impl<S: MeshSelector> MeshSelector for AutoMask<S> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let WeightedSelection { selection, weights } = self.selector.select(mesh_graph, input);
+
+        let mut weights = weights.to_per_vertex(mesh_graph, &selection);
+        weights.retain(|_, weight| *weight > 0.0);
+
+        if self.face_set {
+            match (input.surface, input.attributes) {
+                (Some(surface), Some(attributes)) => {
+                    let face_set = attributes.face_set(surface.face.id);
+
+                    weights.retain(|&vertex_id, _| {
+                        mesh_graph.vertices.get(vertex_id).is_some_and(|vertex| {
+                            vertex
+                                .faces(mesh_graph)
+                                .any(|face_id| attributes.face_set(face_id) == face_set)
+                        })
+                    });
+                }
+                _ => warn!("Face set masking requires the picked face and attributes. Skipping it"),
+            }
+        }
+
+        if self.topology {
+            match input.surface {
+                Some(surface) => {
+                    let connected =
+                        connected_vertices(mesh_graph, surface.face.vertices(mesh_graph), &weights);
+
+                    weights.retain(|vertex_id, _| connected.contains(vertex_id));
+                }
+                None => warn!("Topology masking requires the picked face. Skipping it"),
+            }
+        }
+
+        if self.cavity_factor != 0.0 {
+            let sign = if self.invert_cavity { -1.0 } else { 1.0 };
+
+            for (&vertex_id, weight) in weights.iter_mut() {
+                let cavity = cavity(mesh_graph, vertex_id).unwrap_or_default();
+                *weight *= 1.0 - (sign * cavity * self.cavity_factor).clamp(0.0, 1.0);
+            }
+        }
+
+        if self.boundary_distance > 0.0 {
+            attenuate_near_boundary(mesh_graph, &mut weights, self.boundary_distance);
+        }
+
+        weights.retain(|_, weight| *weight > 0.0);
+
+        WeightedSelection {
+            selection: Selection {
+                faces: faces_incident_to_vertices(weights.keys().copied(), mesh_graph),
+                ..Default::default()
+            },
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
+
+/// Flood fills the vertices that are connected to `start` by edges between vertices of `allowed`.
+fn connected_vertices(
+    mesh_graph: &MeshGraph,
+    start: impl IntoIterator<Item = VertexId>,
+    allowed: &HashMap<VertexId, f32>,
+) -> HashSet<VertexId> {
+    let mut stack = start
+        .into_iter()
+        .filter(|vertex_id| allowed.contains_key(vertex_id))
+        .collect::<Vec<_>>();
+    let mut connected = stack.iter().copied().collect::<HashSet<_>>();
+
+    while let Some(vertex_id) = stack.pop() {
+        let Some(vertex) = mesh_graph
+            .vertices
+            .get(vertex_id)
+            .or_else(error_none!("Vertex not found"))
+        else {
+            continue;
+        };
+
+        for neighbour in vertex.neighbours(mesh_graph) {
+            if allowed.contains_key(&neighbour) && connected.insert(neighbour) {
+                stack.push(neighbour);
+            }
+        }
+    }
+
+    connected
+}
+
+/// Scales the weights down linearly towards the boundary of the mesh.
+///
+/// The boundary doesn't have to be part of the selection. All boundary vertices within
+/// `boundary_distance` of the selected vertices are taken into account.
+fn attenuate_near_boundary(
+    mesh_graph: &MeshGraph,
+    weights: &mut HashMap<VertexId, f32>,
+    boundary_distance: f32,
+) {
+    let surrounding = geodesic_distances(
+        mesh_graph,
+        weights.keys().map(|&vertex_id| (vertex_id, 0.0)),
+        boundary_distance,
+    );
+
+    let boundary = surrounding
+        .into_iter()
+        .filter(|&(vertex_id, distance)| {
+            distance <= boundary_distance && is_boundary_vertex(vertex_id, mesh_graph)
+        })
+        .map(|(vertex_id, _)| (vertex_id, 0.0))
+        .collect::<Vec<_>>();
+
+    if boundary.is_empty() {
+        return;
+    }
+
+    let distances = geodesic_distances(mesh_graph, boundary, boundary_distance);
+
+    for (vertex_id, weight) in weights.iter_mut() {
+        if let Some(distance) = distances.get(vertex_id) {
+            *weight *= distance / boundary_distance;
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use hashbrown::HashSet;
+use mesh_graph::{FaceId, MeshGraph, Selection};
+use tracing::instrument;
+
+use super::{MeshSelector, SelectionInput, SelectionWeights, WeightedSelection};
+
+/// Selects everything that is selected by either of the two selectors.
+///
+/// The weight of a vertex is the maximum of both weights.
+#[derive(Debug, Clone, Copy)]
+pub struct Union<A: MeshSelector, B: MeshSelector>(pub A, pub B);
+
+impl<A: MeshSelector, B: MeshSelector> MeshSelector for Union<A, B> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let a = self.0.select(mesh_graph, input);
+        let b = self.1.select(mesh_graph, input);
+
+        let faces = a
+            .selection
+            .faces
+            .union(&b.selection.faces)
+            .copied()
+            .collect();
+
+        combine(mesh_graph, faces, a, b, f32::max)
+    }
+}
+
+/// Selects only what is selected by both selectors.
+///
+/// The weight of a vertex is the minimum of both weights.
+#[derive(Debug, Clone, Copy)]
+pub struct Intersect<A: MeshSelector, B: MeshSelector>(pub A, pub B);
+
+impl<A: MeshSelector, B: MeshSelector> MeshSelector for Intersect<A, B> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let a = self.0.select(mesh_graph, input);
+        let b = self.1.select(mesh_graph, input);
+
+        let faces = a
+            .selection
+            .faces
+            .intersection(&b.selection.faces)
+            .copied()
+            .collect();
+
+        combine(mesh_graph, faces, a, b, f32::min)
+    }
+}
+
+/// Removes what is selected by the second selector from the selection of the first one.
+///
+/// The weight of a vertex is the first weight multiplied by one minus the second weight.
+/// So vertices in the falloff of the second selector are partially removed.
+#[derive(Debug, Clone, Copy)]
+pub struct Subtract<A: MeshSelector, B: MeshSelector>(pub A, pub B);
+
+impl<A: MeshSelector, B: MeshSelector> MeshSelector for Subtract<A, B> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let a = self.0.select(mesh_graph, input);
+        let b = self.1.select(mesh_graph, input);
+
+        let faces = a.selection.faces.clone();
+
+        combine(mesh_graph, faces, a, b, |a, b| {
+            a * (1.0 - b).clamp(0.0, 1.0)
+        })
+    }
+}
+
+/// Selects only what is selected by both selectors like [`Intersect`].
+///
+/// The weight of a vertex is the product of both weights. Use this to mask a selector with another one.
+#[derive(Debug, Clone, Copy)]
+pub struct Multiply<A: MeshSelector, B: MeshSelector>(pub A, pub B);
+
+impl<A: MeshSelector, B: MeshSelector> MeshSelector for Multiply<A, B> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let a = self.0.select(mesh_graph, input);
+        let b = self.1.select(mesh_graph, input);
+
+        let faces = a
+            .selection
+            .faces
+            .intersection(&b.selection.faces)
+            .copied()
+            .collect();
+
+        combine(mesh_graph, faces, a, b, |a, b| a * b)
+    }
+}
+
+/// Combines the weights of two selections with `op` for the vertices of the given faces.
+///
+/// Both weights are evaluated only for the vertices of their own selection. Vertices outside of a selection
+/// have a weight of zero for it, even if a callback would return something else for their position.
+fn combine(
+    mesh_graph: &MeshGraph,
+    faces: HashSet<FaceId>,
+    a: WeightedSelection,
+    b: WeightedSelection,
+    op: fn(f32, f32) -> f32,
+) -> WeightedSelection {
+    let a = a.weights.to_per_vertex(mesh_graph, &a.selection);
+    let b = b.weights.to_per_vertex(mesh_graph, &b.selection);
+
+    let selection = Selection {
+        faces,
+        ..Default::default()
+    };
+
+    let weights = selection
+        .resolve_to_vertices(mesh_graph)
+        .into_iter()
+        .filter_map(|vertex_id| {
+            let weight = op(
+                a.get(&vertex_id).copied().unwrap_or_default(),
+                b.get(&vertex_id).copied().unwrap_or_default(),
+            );
+
+            (weight > 0.0).then_some((vertex_id, weight))
+        })
+        .collect();
+
+    WeightedSelection {
+        selection,
+        weights: SelectionWeights::PerVertex(weights),
+    }
+}
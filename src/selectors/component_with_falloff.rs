@@ -0,0 +1,173 @@
+use hashbrown::HashSet;
+use mesh_graph::{FaceId, MeshGraph, Selection, VertexId};
+use tracing::{error, instrument};
+
+use crate::{attributes::MeshAttributes, ray::FaceIntersection};
+
+use super::{
+    FalloffCurve, MeshSelector, SelectionInput, SelectionWeights, WeightedSelection,
+    geodesic_distances,
+};
+
+/// Selects the whole connected part of the mesh that contains the picked face.
+///
+/// With [`ComponentWithFalloff::face_set`] the selection is limited to the face set of the picked face
+/// (see [`MeshAttributes::face_sets`]). This requires [`SelectionInput::attributes`], so the attributes have to be
+/// passed to [`DeformationField::on_pointer_down`](crate::deformation::DeformationField::on_pointer_down)
+/// and [`DeformationField::on_pointer_move`](crate::deformation::DeformationField::on_pointer_move).
+///
+/// This is useful to move separate pieces of a multi-part sculpt with
+/// [`TranslateDeformation`](crate::deformation::TranslateDeformation) without affecting the others.
+#[derive(Debug, Clone)]
+pub struct ComponentWithFalloff {
+    /// The geodesic distance from the border of the selected part in which the influence
+    /// increases from zero to full. The way the influence increases is controlled by `falloff_curve`.
+    ///
+    /// Closed parts don't have a border so this only matters for open meshes and face sets.
+    pub falloff: f32,
+
+    /// The falloff curve used to calculate the weight of the selection.
+    pub falloff_curve: FalloffCurve,
+
+    /// If `true` the selection doesn't leave the face set of the picked face.
+    pub face_set: bool,
+}
+
+impl ComponentWithFalloff {
+    /// Creates a new `ComponentWithFalloff` selector that selects the connected component of the picked face.
+    #[inline]
+    pub fn component(falloff: f32, falloff_curve: impl Into<FalloffCurve>) -> Self {
+        Self {
+            falloff,
+            falloff_curve: falloff_curve.into(),
+            face_set: false,
+        }
+    }
+
+    /// Creates a new `ComponentWithFalloff` selector that selects the connected part of the face set of the picked face.
+    #[inline]
+    pub fn face_set(falloff: f32, falloff_curve: impl Into<FalloffCurve>) -> Self {
+        Self {
+            falloff,
+            falloff_curve: falloff_curve.into(),
+            face_set: true,
+        }
+    }
+}
+
+impl MeshSelector for ComponentWithFalloff {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            face: input_face, ..
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
+        let face_set_attributes = if self.face_set {
+            let Some(attributes) = input.attributes else {
+                error!("Face set selection requires attributes");
+                return WeightedSelection::default();
+            };
+
+            Some(attributes)
+        } else {
+            None
+        };
+
+        let (faces, border) = connected_faces(mesh_graph, input_face.id, face_set_attributes);
+
+        let vertices = faces
+            .iter()
+            .filter_map(|&face_id| mesh_graph.faces.get(face_id))
+            .flat_map(|face| face.vertices(mesh_graph))
+            .collect::<HashSet<_>>();
+
+        let distances = if self.falloff > 0.0 {
+            geodesic_distances(
+                mesh_graph,
+                border.into_iter().map(|vertex_id| (vertex_id, 0.0)),
+                self.falloff,
+            )
+        } else {
+            Default::default()
+        };
+
+        let weights = vertices
+            .into_iter()
+            .map(|vertex_id| {
+                let weight = distances
+                    .get(&vertex_id)
+                    .map(|distance| self.falloff_curve.evaluate(distance / self.falloff))
+                    .unwrap_or(1.0);
+
+                (vertex_id, weight)
+            })
+            .collect();
+
+        WeightedSelection {
+            selection: Selection {
+                faces,
+                ..Default::default()
+            },
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
+
+/// Flood fills the faces that are connected to `start_face` by edges.
+///
+/// If `attributes` are given, only faces in the same face set as `start_face` are filled.
+/// Returns the faces and the vertices on the border of the filled faces.
+fn connected_faces(
+    mesh_graph: &MeshGraph,
+    start_face: FaceId,
+    attributes: Option<&MeshAttributes>,
+) -> (HashSet<FaceId>, HashSet<VertexId>) {
+    let face_set = attributes.map(|attributes| attributes.face_set(start_face));
+
+    let mut faces = HashSet::from_iter([start_face]);
+    let mut border = HashSet::new();
+    let mut stack = vec![start_face];
+
+    while let Some(face_id) = stack.pop() {
+        let Some(face) = mesh_graph.faces.get(face_id) else {
+            error!("Face not found");
+            continue;
+        };
+
+        for he_id in face.halfedges(mesh_graph) {
+            let Some(he) = mesh_graph.halfedges.get(he_id) else {
+                error!("Halfedge not found");
+                continue;
+            };
+
+            let neighbour = he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .and_then(|twin| twin.face)
+                .filter(|&neighbour| {
+                    attributes
+                        .zip(face_set)
+                        .is_none_or(|(attributes, face_set)| {
+                            attributes.face_set(neighbour) == face_set
+                        })
+                });
+
+            match neighbour {
+                Some(neighbour) => {
+                    if faces.insert(neighbour) {
+                        stack.push(neighbour);
+                    }
+                }
+                None => {
+                    border.insert(he.end_vertex);
+                    border.extend(he.start_vertex(mesh_graph));
+                }
+            }
+        }
+    }
+
+    (faces, border)
+}
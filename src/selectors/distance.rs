@@ -1,7 +1,8 @@
-use glam::Vec3;
+use glam::{Mat3, Quat, Vec3};
 
 use super::DistanceCalculator;
 
+/// The euclidean distance. Selects a sphere.
 #[derive(Debug, Clone, Copy)]
 pub struct L2;
 
@@ -10,3 +11,139 @@ impl DistanceCalculator for L2 {
         a.distance_squared(b)
     }
 }
+
+/// The Manhattan distance, i.e. the sum of the distances along the axes. Selects an axis aligned octahedron.
+#[derive(Debug, Clone, Copy)]
+pub struct L1;
+
+impl DistanceCalculator for L1 {
+    fn distance_squared(&self, a: Vec3, b: Vec3) -> f32 {
+        let distance = (b - a).abs().element_sum();
+        distance * distance
+    }
+}
+
+/// The Chebyshev distance, i.e. the largest of the distances along the axes. Selects an axis aligned cube.
+#[derive(Debug, Clone, Copy)]
+pub struct LInf;
+
+impl DistanceCalculator for LInf {
+    fn distance_squared(&self, a: Vec3, b: Vec3) -> f32 {
+        let distance = (b - a).abs().max_element();
+        distance * distance
+    }
+}
+
+/// An oriented ellipsoid.
+///
+/// The distance along each axis of the ellipsoid is divided by the corresponding component of `scale`.
+/// So with a radius `r` the selection is an ellipsoid with the radii `r * scale`.
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipsoid {
+    /// The rotation of the ellipsoid's axes.
+    pub orientation: Quat,
+
+    /// The relative radii along the ellipsoid's x, y and z axes.
+    pub scale: Vec3,
+
+    /// If `true` the ellipsoid is rotated so that its z axis is aligned to the surface normal at the input position.
+    /// `orientation` is ignored in this case.
+    pub align_to_normal: bool,
+}
+
+impl Ellipsoid {
+    /// Creates a new ellipsoid with a fixed orientation.
+    #[inline]
+    pub fn new(orientation: Quat, scale: Vec3) -> Self {
+        Self {
+            orientation,
+            scale,
+            align_to_normal: false,
+        }
+    }
+
+    /// Creates an ellipsoid that is flattened along the surface normal, like a disc that lies on the surface.
+    ///
+    /// `flatness` is the relative radius along the normal. For example with `0.25` the brush
+    /// only reaches a quarter of its radius into and out of the surface.
+    #[inline]
+    pub fn flattened(flatness: f32) -> Self {
+        Self {
+            orientation: Quat::IDENTITY,
+            scale: Vec3::new(1.0, 1.0, flatness),
+            align_to_normal: true,
+        }
+    }
+
+    #[inline]
+    fn axes(&self) -> Mat3 {
+        Mat3::from_quat(self.orientation) * Mat3::from_diagonal(self.scale)
+    }
+}
+
+impl DistanceCalculator for Ellipsoid {
+    fn distance_squared(&self, a: Vec3, b: Vec3) -> f32 {
+        (self.orientation.inverse() * (b - a) / self.scale).length_squared()
+    }
+
+    fn at_surface(&self, normal: Vec3) -> Self {
+        if self.align_to_normal
+            && let Some(normal) = normal.try_normalize()
+        {
+            Self {
+                orientation: Quat::from_rotation_arc(Vec3::Z, normal),
+                ..*self
+            }
+        } else {
+            *self
+        }
+    }
+
+    fn half_extents(&self, distance: f32) -> Vec3 {
+        let axes = self.axes();
+
+        distance
+            * Vec3::new(
+                axes.row(0).length(),
+                axes.row(1).length(),
+                axes.row(2).length(),
+            )
+    }
+}
+
+/// The distance to a line segment that ends at the input position. Selects a capsule.
+///
+/// Set `segment` to the pointer translation to select everything along the last stroke segment,
+/// so fast strokes don't leave gaps.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule {
+    /// The vector from the start of the segment to the input position.
+    pub segment: Vec3,
+}
+
+impl Capsule {
+    /// Creates a new capsule along the given stroke segment.
+    #[inline]
+    pub fn new(segment: Vec3) -> Self {
+        Self { segment }
+    }
+}
+
+impl DistanceCalculator for Capsule {
+    fn distance_squared(&self, a: Vec3, b: Vec3) -> f32 {
+        let start = a - self.segment;
+        let length_squared = self.segment.length_squared();
+
+        if length_squared <= f32::EPSILON {
+            return a.distance_squared(b);
+        }
+
+        let t = ((b - start).dot(self.segment) / length_squared).clamp(0.0, 1.0);
+
+        (start + self.segment * t).distance_squared(b)
+    }
+
+    fn half_extents(&self, distance: f32) -> Vec3 {
+        Vec3::splat(distance) + self.segment.abs()
+    }
+}
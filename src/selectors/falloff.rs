@@ -0,0 +1,264 @@
+use glam::Vec2;
+
+pub type FalloffFn = fn(f32) -> f32;
+
+pub const LINEAR_FALLOFF: FalloffFn = |x| x;
+
+pub const SMOOTH_FALLOFF: FalloffFn = |x| {
+    let x2 = x * x;
+    3.0 * x2 - 2.0 * x2 * x
+};
+
+/// The profile of a brush, i.e. how the influence decreases within the falloff.
+///
+/// It maps values from 0.0 (outer end of the falloff) to 1.0 (full influence) to a weight in the same range.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FalloffCurve {
+    /// Full influence up to the outer end of the falloff.
+    Constant,
+
+    /// The influence decreases linearly.
+    Linear,
+
+    /// Smoothstep. The influence decreases slowly at both ends of the falloff.
+    #[default]
+    Smooth,
+
+    /// The influence decreases quickly after the radius, `x²`.
+    Sharp,
+
+    /// The influence decreases slowly after the radius and quickly at the end, `√x`.
+    Root,
+
+    /// The profile of a sphere, `√(2x - x²)`.
+    Sphere,
+
+    /// Inverse square, `x (2 - x)`.
+    InverseSquare,
+
+    /// A monotone cubic spline through the given control points.
+    ///
+    /// The points have to be sorted by their x coordinate. Outside of the points the curve is constant.
+    Spline(Vec<Vec2>),
+
+    /// A cubic Bézier curve from `(0, 0)` to `(1, 1)` with the two given control points like CSS `cubic-bezier`.
+    ///
+    /// The x coordinates of the control points have to be in the range 0.0 to 1.0.
+    Bezier(Vec2, Vec2),
+
+    /// Any function. This can't be serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(FalloffFn),
+}
+
+impl From<FalloffFn> for FalloffCurve {
+    fn from(falloff_func: FalloffFn) -> Self {
+        Self::Custom(falloff_func)
+    }
+}
+
+impl FalloffCurve {
+    /// Evaluates the curve at `x`, which is clamped to the range 0.0 to 1.0.
+    pub fn evaluate(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+
+        let y = match self {
+            Self::Constant => 1.0,
+            Self::Linear => x,
+            Self::Smooth => SMOOTH_FALLOFF(x),
+            Self::Sharp => x * x,
+            Self::Root => x.sqrt(),
+            Self::Sphere => (2.0 * x - x * x).sqrt(),
+            Self::InverseSquare => x * (2.0 - x),
+            Self::Spline(points) => evaluate_spline(points, x),
+            Self::Bezier(a, b) => evaluate_bezier(*a, *b, x),
+            Self::Custom(falloff_func) => falloff_func(x),
+        };
+
+        y.clamp(0.0, 1.0)
+    }
+}
+
+/// Weight of a vertex at `distance` from the center of a brush with the given radius and falloff.
+pub(crate) fn falloff_weight(
+    distance: f32,
+    radius: f32,
+    falloff: f32,
+    falloff_curve: &FalloffCurve,
+) -> f32 {
+    let rf = radius + falloff;
+
+    if distance <= radius {
+        1.0
+    } else if distance <= rf {
+        falloff_curve.evaluate((rf - distance) / falloff)
+    } else {
+        0.0
+    }
+}
+
+/// Monotone cubic interpolation (Fritsch-Carlson) so the curve doesn't overshoot between the control points.
+fn evaluate_spline(points: &[Vec2], x: f32) -> f32 {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return x;
+    };
+
+    if x <= first.x {
+        return first.y;
+    }
+    if x >= last.x {
+        return last.y;
+    }
+
+    let secant = |i: usize| {
+        let dx = points[i + 1].x - points[i].x;
+        if dx > f32::EPSILON {
+            (points[i + 1].y - points[i].y) / dx
+        } else {
+            0.0
+        }
+    };
+
+    let tangent = |i: usize| {
+        if i == 0 {
+            secant(0)
+        } else if i == points.len() - 1 {
+            secant(i - 1)
+        } else {
+            let (before, after) = (secant(i - 1), secant(i));
+
+            if before * after <= 0.0 {
+                0.0
+            } else {
+                // harmonic mean keeps the interpolation monotone
+                2.0 / (1.0 / before + 1.0 / after)
+            }
+        }
+    };
+
+    let i = points
+        .windows(2)
+        .position(|window| x < window[1].x)
+        .unwrap_or(points.len() - 2);
+
+    let (a, b) = (points[i], points[i + 1]);
+    let dx = b.x - a.x;
+
+    if dx <= f32::EPSILON {
+        return b.y;
+    }
+
+    let t = (x - a.x) / dx;
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    (2.0 * t3 - 3.0 * t2 + 1.0) * a.y
+        + (t3 - 2.0 * t2 + t) * dx * tangent(i)
+        + (-2.0 * t3 + 3.0 * t2) * b.y
+        + (t3 - t2) * dx * tangent(i + 1)
+}
+
+/// Finds the parameter of the Bézier curve at `x` by bisection and returns the y coordinate there.
+fn evaluate_bezier(a: Vec2, b: Vec2, x: f32) -> f32 {
+    let point = |t: f32| {
+        let s = 1.0 - t;
+        3.0 * s * s * t * a + 3.0 * s * t * t * b + Vec2::splat(t * t * t)
+    };
+
+    let (mut low, mut high) = (0.0, 1.0);
+
+    for _ in 0..24 {
+        let mid = (low + high) * 0.5;
+
+        if point(mid).x < x {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    point((low + high) * 0.5).y
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::vec2;
+
+    use super::*;
+
+    fn samples(curve: &FalloffCurve) -> Vec<f32> {
+        (0..=1000)
+            .map(|i| curve.evaluate(i as f32 / 1000.0))
+            .collect()
+    }
+
+    #[test]
+    fn spline_passes_through_points() {
+        let points = vec![
+            vec2(0.0, 0.0),
+            vec2(0.3, 0.8),
+            vec2(0.7, 0.9),
+            vec2(1.0, 1.0),
+        ];
+        let curve = FalloffCurve::Spline(points.clone());
+
+        for point in points {
+            assert!((curve.evaluate(point.x) - point.y).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn spline_is_monotone_without_overshoot() {
+        // a steep rise into a plateau makes ordinary cubic splines overshoot
+        let curve = FalloffCurve::Spline(vec![
+            vec2(0.0, 0.0),
+            vec2(0.2, 0.1),
+            vec2(0.3, 1.0),
+            vec2(0.7, 1.0),
+            vec2(1.0, 1.0),
+        ]);
+
+        let values = samples(&curve);
+
+        assert!(values.windows(2).all(|pair| pair[1] >= pair[0] - 1e-6));
+
+        for (i, value) in values.iter().enumerate() {
+            let x = i as f32 / 1000.0;
+
+            if x >= 0.3 {
+                assert!((value - 1.0).abs() < 1e-6, "overshoot at {x}: {value}");
+            }
+        }
+    }
+
+    #[test]
+    fn spline_is_constant_outside_of_points() {
+        let curve = FalloffCurve::Spline(vec![vec2(0.2, 0.3), vec2(0.8, 0.6)]);
+
+        assert_eq!(curve.evaluate(0.0), 0.3);
+        assert_eq!(curve.evaluate(0.1), 0.3);
+        assert_eq!(curve.evaluate(0.9), 0.6);
+        assert_eq!(curve.evaluate(1.0), 0.6);
+    }
+
+    #[test]
+    fn bezier_hits_end_points() {
+        let curve = FalloffCurve::Bezier(vec2(0.25, 0.1), vec2(0.25, 1.0));
+
+        assert!(curve.evaluate(0.0).abs() < 1e-5);
+        assert!((curve.evaluate(1.0) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn bezier_matches_css_ease() {
+        // CSS `ease` is `cubic-bezier(0.25, 0.1, 0.25, 1.0)`
+        let ease = FalloffCurve::Bezier(vec2(0.25, 0.1), vec2(0.25, 1.0));
+        assert!((ease.evaluate(0.5) - 0.802_403_4).abs() < 1e-4);
+
+        // `ease-in-out` is symmetric around the center
+        let ease_in_out = FalloffCurve::Bezier(vec2(0.42, 0.0), vec2(0.58, 1.0));
+        assert!((ease_in_out.evaluate(0.5) - 0.5).abs() < 1e-4);
+        assert!((ease_in_out.evaluate(0.25) + ease_in_out.evaluate(0.75) - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,84 @@
+use glam::Vec3;
+use mesh_graph::MeshGraph;
+use tracing::instrument;
+
+use crate::utils::{face_area_normal, vertex_normal};
+
+use super::{MeshSelector, SelectionInput, WeightedSelection, retain_vertices};
+
+/// What the vertex normals are compared against by [`FrontFacing`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FacingReference {
+    /// Vertices are kept if they face the viewer looking along this direction.
+    /// This has to be in the local space of the mesh graph.
+    ViewDirection(Vec3),
+
+    /// Vertices are kept if they face the same side as the face under the pointer.
+    PickedNormal,
+}
+
+/// Wraps another selector and removes all vertices whose normals face away from the viewer or the picked face.
+///
+/// This prevents deforming the back side of thin walls that are within the brush radius.
+///
+/// The weights are converted to [`SelectionWeights::PerVertex`](super::SelectionWeights::PerVertex) so they don't change while deforming.
+#[derive(Debug, Clone, Copy)]
+pub struct FrontFacing<S: MeshSelector> {
+    /// The wrapped selector.
+    pub selector: S,
+
+    /// What the vertex normals are compared against.
+    pub reference: FacingReference,
+
+    /// The minimum cosine of the angle between a vertex normal and the reference direction.
+    /// `0.0` keeps every vertex that faces the reference side at all.
+    pub min_cos_angle: f32,
+}
+
+impl<S: MeshSelector> FrontFacing<S> {
+    /// Creates a new `FrontFacing` selector that keeps every vertex facing the reference side.
+    #[inline]
+    pub fn new(selector: S, reference: FacingReference) -> Self {
+        Self {
+            selector,
+            reference,
+            min_cos_angle: 0.0,
+        }
+    }
+
+    /// Creates a new `FrontFacing` selector that keeps only the vertices whose normals deviate at most
+    /// `max_angle` (in radians) from the normal of the picked face.
+    ///
+    /// Use this for hard-surface work so that flattening near an edge doesn't affect the faces around the corner.
+    #[inline]
+    pub fn normal_cone(selector: S, max_angle: f32) -> Self {
+        Self {
+            selector,
+            reference: FacingReference::PickedNormal,
+            min_cos_angle: max_angle.cos(),
+        }
+    }
+}
+
+impl<S: MeshSelector> MeshSelector for FrontFacing<S> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let front = match self.reference {
+            FacingReference::ViewDirection(view_direction) => -view_direction,
+            FacingReference::PickedNormal => input
+                .surface
+                .and_then(|surface| face_area_normal(surface.face.id, mesh_graph))
+                .unwrap_or_default(),
+        }
+        .normalize_or_zero();
+
+        retain_vertices(
+            mesh_graph,
+            self.selector.select(mesh_graph, input),
+            |vertex_id, _| {
+                vertex_normal(vertex_id, mesh_graph)
+                    .is_some_and(|normal| normal.dot(front) >= self.min_cos_angle)
+            },
+        )
+    }
+}
@@ -0,0 +1,271 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::Vec3;
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{MeshGraph, Selection, VertexId, error_none};
+use tracing::{error, instrument};
+
+use crate::ray::FaceIntersection;
+
+use super::{
+    FalloffCurve, MeshSelector, SelectionInput, SelectionWeights, WeightedSelection,
+    faces_incident_to_vertices, falloff_weight,
+};
+
+/// Generates a selection on the surface of a mesh based on the geodesic distance to the input point,
+/// i.e. the length of the shortest path along the surface.
+///
+/// Compared to [`SurfaceMetricWithFalloff`](super::SurfaceMetricWithFalloff) the geodesic distance is also
+/// used for the weights. Brushing near thin folds like lips or fingers doesn't bleed across to the other side.
+///
+/// The distances are computed with the fast marching method. The weights are
+/// [`SelectionWeights::PerVertex`] so they stay the same while deforming.
+#[derive(Debug, Clone)]
+pub struct GeodesicWithFalloff {
+    /// The geodesic radius of full influence.
+    pub radius: f32,
+
+    /// The falloff distance. This means that the influence decreases from the
+    /// radius to the radius + falloff along the surface.
+    /// The way the influence decreases is controlled by `falloff_curve`.
+    pub falloff: f32,
+
+    /// The falloff curve used to calculate the weight of the selection.
+    pub falloff_curve: FalloffCurve,
+}
+
+impl GeodesicWithFalloff {
+    /// Creates a new `GeodesicWithFalloff` selector.
+    #[inline]
+    pub fn new(radius: f32, falloff: f32, falloff_curve: impl Into<FalloffCurve>) -> Self {
+        Self {
+            radius,
+            falloff,
+            falloff_curve: falloff_curve.into(),
+        }
+    }
+}
+
+impl MeshSelector for GeodesicWithFalloff {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            point: input_pos,
+            face: input_face,
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
+        let sources = input_face
+            .vertices(mesh_graph)
+            .filter_map(|vertex_id| {
+                mesh_graph
+                    .positions
+                    .get(vertex_id)
+                    .or_else(error_none!("Vertex position not found"))
+                    .map(|pos| (vertex_id, pos.distance(input_pos)))
+            })
+            .collect::<Vec<_>>();
+
+        let distances = geodesic_distances(mesh_graph, sources, self.radius + self.falloff);
+
+        let weights = distances
+            .iter()
+            .map(|(&vertex_id, &distance)| {
+                let weight =
+                    falloff_weight(distance, self.radius, self.falloff, &self.falloff_curve);
+                (vertex_id, weight)
+            })
+            .collect();
+
+        WeightedSelection {
+            selection: Selection {
+                faces: faces_incident_to_vertices(distances.keys().copied(), mesh_graph),
+                ..Default::default()
+            },
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
+
+/// Computes the geodesic distances of all vertices up to `max_distance` away from the sources
+/// with the fast marching method.
+///
+/// The sources are vertices with their initial distances. Returns the distances of all vertices
+/// that are at most `max_distance` away.
+#[instrument(skip_all)]
+pub(crate) fn geodesic_distances(
+    mesh_graph: &MeshGraph,
+    sources: impl IntoIterator<Item = (VertexId, f32)>,
+    max_distance: f32,
+) -> HashMap<VertexId, f32> {
+    let mut distances = HashMap::<VertexId, f32>::new();
+    let mut accepted = HashSet::new();
+    let mut queue = BinaryHeap::new();
+
+    for (vertex, distance) in sources {
+        if distances.get(&vertex).is_none_or(|&d| distance < d) {
+            distances.insert(vertex, distance);
+            queue.push(MarchingFront { distance, vertex });
+        }
+    }
+
+    while let Some(MarchingFront { distance, vertex }) = queue.pop() {
+        if distance > max_distance {
+            break;
+        }
+
+        if !accepted.insert(vertex) {
+            continue;
+        }
+
+        let Some(vertex) = mesh_graph
+            .vertices
+            .get(vertex)
+            .or_else(error_none!("Vertex not found"))
+        else {
+            continue;
+        };
+
+        for face_id in vertex.faces(mesh_graph) {
+            let Some(face) = mesh_graph.faces.get(face_id) else {
+                error!("Face not found");
+                continue;
+            };
+
+            let face_vertices = face.vertices(mesh_graph).collect::<Vec<_>>();
+            let [a, b, c] = face_vertices[..] else {
+                continue;
+            };
+
+            for (target, first, second) in [(a, b, c), (b, c, a), (c, a, b)] {
+                if accepted.contains(&target) {
+                    continue;
+                }
+
+                let Some(candidate) =
+                    updated_distance(mesh_graph, &distances, &accepted, target, first, second)
+                else {
+                    continue;
+                };
+
+                if distances.get(&target).is_none_or(|&d| candidate < d) {
+                    distances.insert(target, candidate);
+                    queue.push(MarchingFront {
+                        distance: candidate,
+                        vertex: target,
+                    });
+                }
+            }
+        }
+    }
+
+    distances.retain(|vertex, _| accepted.contains(vertex));
+    distances
+}
+
+/// Distance of `target` computed from the accepted vertices `first` and `second` of the same triangle.
+fn updated_distance(
+    mesh_graph: &MeshGraph,
+    distances: &HashMap<VertexId, f32>,
+    accepted: &HashSet<VertexId>,
+    target: VertexId,
+    first: VertexId,
+    second: VertexId,
+) -> Option<f32> {
+    let target_pos = *mesh_graph.positions.get(target)?;
+
+    let known = |vertex_id: VertexId| {
+        accepted
+            .contains(&vertex_id)
+            .then(|| Some((*mesh_graph.positions.get(vertex_id)?, distances[&vertex_id])))
+            .flatten()
+    };
+
+    let first = known(first);
+    let second = known(second);
+
+    // along the edges
+    let mut distance = [first, second]
+        .into_iter()
+        .flatten()
+        .map(|(pos, d)| d + pos.distance(target_pos))
+        .reduce(f32::min)?;
+
+    // across the triangle
+    if let (Some((a, ta)), Some((b, tb))) = (first, second)
+        && let Some(d) = unfolded_distance(a, ta, b, tb, target_pos)
+    {
+        distance = distance.min(d);
+    }
+
+    Some(distance)
+}
+
+/// Unfolds the triangle `a`, `b`, `c` into the plane and computes the distance of `c` to the virtual point source
+/// that is `ta` away from `a` and `tb` away from `b`.
+///
+/// Returns `None` if the shortest path from the source to `c` doesn't cross the edge from `a` to `b`.
+fn unfolded_distance(a: Vec3, ta: f32, b: Vec3, tb: f32, c: Vec3) -> Option<f32> {
+    let ab = b - a;
+    let len = ab.length();
+
+    if len <= f32::EPSILON {
+        return None;
+    }
+
+    // 2D coordinates with `a` at the origin and `b` on the positive x-axis
+    let ac = c - a;
+    let cx = ac.dot(ab) / len;
+    let cy = (ac.length_squared() - cx * cx).max(0.0).sqrt();
+
+    let sx = (ta * ta - tb * tb + len * len) / (2.0 * len);
+    let sy_squared = ta * ta - sx * sx;
+
+    if sy_squared < 0.0 {
+        return None;
+    }
+
+    // the source is on the other side of the edge
+    let sy = -sy_squared.sqrt();
+
+    if cy - sy <= f32::EPSILON {
+        return None;
+    }
+
+    let crossing = sx + (cx - sx) * (-sy / (cy - sy));
+
+    if !(0.0..=len).contains(&crossing) {
+        return None;
+    }
+
+    Some((cx - sx).hypot(cy - sy))
+}
+
+/// A vertex on the front of the fast marching in the priority queue.
+struct MarchingFront {
+    distance: f32,
+    vertex: VertexId,
+}
+
+impl PartialEq for MarchingFront {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MarchingFront {}
+
+impl PartialOrd for MarchingFront {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MarchingFront {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // reversed so the `BinaryHeap` pops the closest vertex first
+        other.distance.total_cmp(&self.distance)
+    }
+}
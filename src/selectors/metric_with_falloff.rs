@@ -1,61 +1,87 @@
-use glam::Vec3;
 use hashbrown::HashSet;
 use parry3d::math::{Point, Vector};
 
-use mesh_graph::{Face, MeshGraph, Selection, error_none};
+use mesh_graph::{MeshGraph, Selection, error_none};
 use tracing::{error, instrument};
 
+use crate::{ray::FaceIntersection, utils::face_area_normal};
+
 use super::{
-    DistanceCalculator, FalloffFn, L2, MeshSelector, WeightedSelection, faces_incident_to_vertices,
-    get_sphere_with_falloff_weight_callback,
+    DistanceCalculator, FalloffCurve, L2, MeshSelector, SelectionInput, SelectionWeights,
+    WeightedSelection, faces_incident_to_vertices, get_sphere_with_falloff_weight_callback,
 };
 
 /// Generates a selection of a mesh that is within a sphere with a falloff
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MetricWithFalloff<D: DistanceCalculator> {
     /// The radius of the sphere.
     pub radius: f32,
 
     /// The falloff distance of the sphere. This means that the influence
     /// decreases from the radius to the radius + falloff.
-    /// The way the influence decreases is controlled by `falloff_func`.
+    /// The way the influence decreases is controlled by `falloff_curve`.
     pub falloff: f32,
 
     /// The metric squared used to calculate the distance between the input position and the vertices.
     pub metric_squared: D,
 
-    /// The falloff function used to calculate the weight of the selection.
-    /// It receives values from 0.0 to 1.0 and has to return a value in the same range.
-    /// Simply returning the input value is a linear falloff.
-    pub falloff_func: FalloffFn,
+    /// The falloff curve used to calculate the weight of the selection.
+    pub falloff_curve: FalloffCurve,
+}
+
+impl<D: DistanceCalculator> MetricWithFalloff<D> {
+    /// Creates a new `MetricWithFalloff` selector with the given metric, e.g. [`Ellipsoid`](super::Ellipsoid) or [`Capsule`](super::Capsule).
+    #[inline]
+    pub fn new(
+        radius: f32,
+        falloff: f32,
+        metric_squared: D,
+        falloff_curve: impl Into<FalloffCurve>,
+    ) -> Self {
+        Self {
+            radius,
+            falloff,
+            metric_squared,
+            falloff_curve: falloff_curve.into(),
+        }
+    }
 }
 
 impl MetricWithFalloff<L2> {
     /// Creates a new `MetricWithFalloff` selector with a sphere metric (normal L2 distance).
     #[inline]
-    pub fn sphere(radius: f32, falloff: f32, falloff_func: FalloffFn) -> Self {
+    pub fn sphere(radius: f32, falloff: f32, falloff_curve: impl Into<FalloffCurve>) -> Self {
         Self {
             radius,
             falloff,
             metric_squared: L2,
-            falloff_func,
+            falloff_curve: falloff_curve.into(),
         }
     }
 }
 
 impl<D: DistanceCalculator + Copy + 'static> MeshSelector for MetricWithFalloff<D> {
     #[instrument(skip(self, mesh_graph))]
-    fn select(
-        &self,
-        mesh_graph: &MeshGraph,
-        input_pos: Vec3,
-        _input_face: Face,
-    ) -> WeightedSelection {
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            point: input_pos,
+            face: input_face,
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
         let mut vertices = HashSet::new();
 
+        let metric_squared = self
+            .metric_squared
+            .at_surface(face_area_normal(input_face.id, mesh_graph).unwrap_or_default());
+
+        let half_extents = metric_squared.half_extents(self.radius + self.falloff);
+
         let aabb = parry3d::bounding_volume::Aabb::from_half_extents(
             Point::new(input_pos.x, input_pos.y, input_pos.z),
-            Vector::from_element(self.radius + self.falloff),
+            Vector::new(half_extents.x, half_extents.y, half_extents.z),
         );
         let potential_faces = mesh_graph.bvh.intersect_aabb(&aabb);
 
@@ -75,7 +101,7 @@ impl<D: DistanceCalculator + Copy + 'static> MeshSelector for MetricWithFalloff<
 
         for vertex_id in potential_selection.resolve_to_vertices(mesh_graph) {
             if let Some(pos) = mesh_graph.positions.get(vertex_id) {
-                let distance = self.metric_squared.distance_squared(*pos, input_pos);
+                let distance = metric_squared.distance_squared(input_pos, *pos);
 
                 if distance <= max_dist_sqr {
                     vertices.insert(vertex_id);
@@ -90,13 +116,13 @@ impl<D: DistanceCalculator + Copy + 'static> MeshSelector for MetricWithFalloff<
                 faces: faces_incident_to_vertices(vertices, mesh_graph),
                 ..Default::default()
             },
-            get_weight: get_sphere_with_falloff_weight_callback(
+            weights: SelectionWeights::Callback(get_sphere_with_falloff_weight_callback(
                 input_pos,
                 self.radius,
                 self.falloff,
-                self.falloff_func,
-                self.metric_squared,
-            ),
+                self.falloff_curve.clone(),
+                metric_squared,
+            )),
         }
     }
 }
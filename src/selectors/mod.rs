@@ -1,50 +1,99 @@
+mod alpha_stamp;
+mod auto_mask;
+mod combinators;
+mod component_with_falloff;
 mod distance;
+mod falloff;
+mod front_facing;
+mod geodesic_with_falloff;
 mod metric_with_falloff;
+mod plane_clip;
+mod ring_with_falloff;
+mod screen_space;
 mod surface_metric_with_falloff;
 mod traits;
 
+pub use alpha_stamp::*;
+pub use auto_mask::*;
+pub use combinators::*;
+pub use component_with_falloff::*;
 pub use distance::*;
+pub use falloff::*;
+pub use front_facing::*;
+pub use geodesic_with_falloff::*;
 pub use metric_with_falloff::*;
+pub use plane_clip::*;
+pub use ring_with_falloff::*;
+pub use screen_space::*;
 pub use surface_metric_with_falloff::*;
 use tracing::{error, instrument};
 pub use traits::*;
 
 use glam::Vec3;
 use hashbrown::HashSet;
-use mesh_graph::{FaceId, MeshGraph, VertexId};
-
-pub type FalloffFn = fn(f32) -> f32;
-
-pub const LINEAR_FALLOFF: FalloffFn = |x| x;
-
-pub const SMOOTH_FALLOFF: FalloffFn = |x| {
-    let x2 = x * x;
-    3.0 * x2 - 2.0 * x2 * x
-};
+use mesh_graph::{FaceId, MeshGraph, Selection, VertexId};
 
 fn get_sphere_with_falloff_weight_callback<D: DistanceCalculator + Copy + 'static>(
     input_pos: Vec3,
     radius: f32,
     falloff: f32,
-    falloff_func: FalloffFn,
+    falloff_curve: FalloffCurve,
     distance_calculator: D,
 ) -> Box<dyn Fn(Vec3) -> f32> {
     Box::new(move |pos: Vec3| {
         let distance = distance_calculator.distance_squared(input_pos, pos).sqrt();
-        let rf = radius + falloff;
+        falloff_weight(distance, radius, falloff, &falloff_curve)
+    })
+}
 
-        if distance <= radius {
-            1.0
-        } else if distance <= rf {
-            falloff_func((rf - distance) / falloff)
-        } else {
-            0.0
-        }
+/// Removes all vertices from the selection for which `keep` returns `false` and all vertices with zero weight.
+///
+/// The weights are converted to [`SelectionWeights::PerVertex`] and the faces are reduced to the ones
+/// incident to the remaining vertices.
+fn retain_vertices(
+    mesh_graph: &MeshGraph,
+    weighted_selection: WeightedSelection,
+    mut keep: impl FnMut(VertexId, Vec3) -> bool,
+) -> WeightedSelection {
+    map_vertex_weights(mesh_graph, weighted_selection, |vertex_id, pos, weight| {
+        if keep(vertex_id, pos) { weight } else { 0.0 }
     })
 }
 
+/// Replaces the weight of every vertex of the selection by the result of `map` and removes all vertices
+/// with zero weight.
+///
+/// The weights are converted to [`SelectionWeights::PerVertex`] and the faces are reduced to the ones
+/// incident to the remaining vertices.
+fn map_vertex_weights(
+    mesh_graph: &MeshGraph,
+    weighted_selection: WeightedSelection,
+    mut map: impl FnMut(VertexId, Vec3, f32) -> f32,
+) -> WeightedSelection {
+    let WeightedSelection { selection, weights } = weighted_selection;
+
+    let mut weights = weights.to_per_vertex(mesh_graph, &selection);
+
+    weights.retain(|&vertex_id, weight| {
+        *weight = match mesh_graph.positions.get(vertex_id) {
+            Some(&pos) if *weight > 0.0 => map(vertex_id, pos, *weight),
+            _ => 0.0,
+        };
+
+        *weight > 0.0
+    });
+
+    WeightedSelection {
+        selection: Selection {
+            faces: faces_incident_to_vertices(weights.keys().copied(), mesh_graph),
+            ..Default::default()
+        },
+        weights: SelectionWeights::PerVertex(weights),
+    }
+}
+
 #[instrument(skip(vertices, mesh_graph))]
-fn faces_incident_to_vertices(
+pub(crate) fn faces_incident_to_vertices(
     vertices: impl IntoIterator<Item = VertexId>,
     mesh_graph: &MeshGraph,
 ) -> HashSet<FaceId> {
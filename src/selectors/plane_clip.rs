@@ -0,0 +1,82 @@
+use glam::Vec3;
+use mesh_graph::MeshGraph;
+use tracing::instrument;
+
+use crate::utils::face_area_normal;
+
+use super::{MeshSelector, SelectionInput, WeightedSelection, retain_vertices};
+
+/// The plane used by [`PlaneClip`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ClipPlane {
+    /// The plane through the picked point that is perpendicular to the normal of the picked face.
+    Brush,
+
+    /// A fixed plane in the local space of the mesh graph.
+    Fixed { point: Vec3, normal: Vec3 },
+}
+
+/// Wraps another selector and removes all vertices below a plane, i.e. on the side its normal points away from.
+///
+/// With [`ClipPlane::Brush`] and a small negative offset this keeps a flatten brush from pulling
+/// up the faces below an edge.
+///
+/// The weights are converted to [`SelectionWeights::PerVertex`](super::SelectionWeights::PerVertex) so
+/// they don't change while deforming.
+#[derive(Debug, Clone, Copy)]
+pub struct PlaneClip<S: MeshSelector> {
+    /// The wrapped selector.
+    pub selector: S,
+
+    /// The plane that separates the kept vertices from the removed ones.
+    pub plane: ClipPlane,
+
+    /// Moves the plane along its normal.
+    pub offset: f32,
+
+    /// If `true` the vertices above the plane are removed instead.
+    pub invert: bool,
+}
+
+impl<S: MeshSelector> PlaneClip<S> {
+    /// Creates a new `PlaneClip` selector that keeps all vertices above the plane.
+    #[inline]
+    pub fn new(selector: S, plane: ClipPlane) -> Self {
+        Self {
+            selector,
+            plane,
+            offset: 0.0,
+            invert: false,
+        }
+    }
+}
+
+impl<S: MeshSelector> MeshSelector for PlaneClip<S> {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let plane = match self.plane {
+            ClipPlane::Brush => input.surface.and_then(|surface| {
+                Some((
+                    surface.point,
+                    face_area_normal(surface.face.id, mesh_graph)?,
+                ))
+            }),
+            ClipPlane::Fixed { point, normal } => Some((point, normal)),
+        };
+
+        let Some((point, normal)) =
+            plane.and_then(|(point, normal)| Some((point, normal.try_normalize()?)))
+        else {
+            return WeightedSelection::default();
+        };
+
+        let sign = if self.invert { -1.0 } else { 1.0 };
+
+        retain_vertices(
+            mesh_graph,
+            self.selector.select(mesh_graph, input),
+            |_, pos| sign * ((pos - point).dot(normal) - self.offset) >= 0.0,
+        )
+    }
+}
@@ -0,0 +1,104 @@
+use hashbrown::{HashMap, HashSet};
+use mesh_graph::{MeshGraph, Selection};
+use tracing::{error, instrument};
+
+use crate::ray::FaceIntersection;
+
+use super::{
+    FalloffCurve, MeshSelector, SelectionInput, SelectionWeights, WeightedSelection,
+    faces_incident_to_vertices, falloff_weight,
+};
+
+/// Selects the vertices that are at most a number of edge hops away from the picked face.
+///
+/// The vertices of the picked face are ring 0, their neighbours ring 1 and so on. The weights only depend on the
+/// ring index and not on the positions of the vertices. This makes it useful for precise low-level edits
+/// and for testing deformations deterministically.
+#[derive(Debug, Clone)]
+pub struct RingWithFalloff {
+    /// The number of rings with full influence.
+    pub rings: usize,
+
+    /// The number of rings after `rings` in which the influence decreases.
+    /// The way the influence decreases is controlled by `falloff_curve`.
+    pub falloff_rings: usize,
+
+    /// The falloff curve used to calculate the weight of the selection.
+    pub falloff_curve: FalloffCurve,
+}
+
+impl RingWithFalloff {
+    /// Creates a new `RingWithFalloff` selector.
+    #[inline]
+    pub fn new(rings: usize, falloff_rings: usize, falloff_curve: impl Into<FalloffCurve>) -> Self {
+        Self {
+            rings,
+            falloff_rings,
+            falloff_curve: falloff_curve.into(),
+        }
+    }
+}
+
+impl MeshSelector for RingWithFalloff {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            face: input_face, ..
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
+        let max_ring = self.rings + self.falloff_rings;
+
+        let mut weights = HashMap::new();
+        let mut ring = input_face.vertices(mesh_graph).collect::<HashSet<_>>();
+
+        for ring_index in 0..=max_ring {
+            let weight = falloff_weight(
+                ring_index as f32,
+                self.rings as f32,
+                (self.falloff_rings + 1) as f32,
+                &self.falloff_curve,
+            );
+
+            for &vertex_id in &ring {
+                weights.insert(vertex_id, weight);
+            }
+
+            if ring_index == max_ring {
+                break;
+            }
+
+            let mut next_ring = HashSet::new();
+
+            for vertex_id in ring {
+                let Some(vertex) = mesh_graph.vertices.get(vertex_id) else {
+                    error!("Vertex not found");
+                    continue;
+                };
+
+                for he_id in vertex.outgoing_halfedges(mesh_graph) {
+                    let Some(he) = mesh_graph.halfedges.get(he_id) else {
+                        error!("Halfedge not found");
+                        continue;
+                    };
+
+                    if !weights.contains_key(&he.end_vertex) {
+                        next_ring.insert(he.end_vertex);
+                    }
+                }
+            }
+
+            ring = next_ring;
+        }
+
+        WeightedSelection {
+            selection: Selection {
+                faces: faces_incident_to_vertices(weights.keys().copied(), mesh_graph),
+                ..Default::default()
+            },
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use glam::{Mat4, Vec2, Vec3};
+use hashbrown::HashMap;
+use mesh_graph::{MeshGraph, Selection};
+use parry3d::query::RayCast;
+use tracing::instrument;
+
+use crate::ray::Ray;
+
+use super::{
+    MeshSelector, SelectionInput, SelectionWeights, WeightedSelection, faces_incident_to_vertices,
+};
+
+/// The shape of a [`ScreenRegion`] in pixels with the origin in the top left corner of the viewport.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScreenShape {
+    /// An axis aligned box between the two corners.
+    Box { min: Vec2, max: Vec2 },
+
+    /// A closed polygon. For a lasso these are the pointer positions of the drag.
+    /// Self-intersecting polygons use the even-odd rule.
+    Polygon(Vec<Vec2>),
+}
+
+impl ScreenShape {
+    /// Creates a box from two arbitrary corners, e.g. where a drag started and where it is now.
+    pub fn from_corners(a: Vec2, b: Vec2) -> Self {
+        Self::Box {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Returns `true` if the point is inside the shape.
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Self::Box { min, max } => point.cmpge(*min).all() && point.cmple(*max).all(),
+            Self::Polygon(points) => {
+                let mut inside = false;
+
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+
+                    if (a.y > point.y) != (b.y > point.y)
+                        && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+                    {
+                        inside = !inside;
+                    }
+                }
+
+                inside
+            }
+        }
+    }
+}
+
+/// A region on the screen together with the view it was drawn in.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScreenRegion {
+    /// The shape of the region in pixels.
+    pub shape: ScreenShape,
+
+    /// The view projection matrix multiplied with the transform of the mesh, i.e. it maps
+    /// the local space of the mesh graph to clip space.
+    pub clip_from_local: Mat4,
+
+    /// The size of the viewport in pixels.
+    pub viewport_size: Vec2,
+
+    /// The position of the camera in the local space of the mesh graph. Only used for occlusion testing.
+    ///
+    /// For orthographic cameras use a point far behind the camera along the view direction.
+    pub camera_position: Vec3,
+}
+
+impl ScreenRegion {
+    /// Projects a point from the local space of the mesh graph to pixels.
+    ///
+    /// Returns `None` if the point is behind the camera.
+    pub fn project(&self, point: Vec3) -> Option<Vec2> {
+        let clip = self.clip_from_local * point.extend(1.0);
+
+        if clip.w <= f32::EPSILON {
+            return None;
+        }
+
+        let ndc = clip.truncate() / clip.w;
+
+        Some(Vec2::new(ndc.x + 1.0, 1.0 - ndc.y) * 0.5 * self.viewport_size)
+    }
+}
+
+/// Selects all vertices whose projections are inside the [`SelectionInput::screen`] region,
+/// e.g. for box, lasso and polygon selections of mask and transform tools.
+///
+/// Selected vertices have a weight of 1.0.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScreenSpace {
+    /// If `true` vertices that are hidden behind other parts of the mesh are not selected.
+    pub visible_only: bool,
+}
+
+impl ScreenSpace {
+    /// Creates a new `ScreenSpace` selector that selects only the vertices visible from the camera.
+    #[inline]
+    pub fn visible_only() -> Self {
+        Self { visible_only: true }
+    }
+}
+
+impl MeshSelector for ScreenSpace {
+    #[instrument(skip(self, mesh_graph))]
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(region) = &input.screen else {
+            return WeightedSelection::default();
+        };
+
+        let weights = mesh_graph
+            .positions
+            .iter()
+            .filter(|(_, pos)| {
+                region
+                    .project(**pos)
+                    .is_some_and(|screen_pos| region.shape.contains(screen_pos))
+            })
+            .filter(|(_, pos)| !self.visible_only || is_visible(mesh_graph, **pos, region))
+            .map(|(vertex_id, _)| (vertex_id, 1.0))
+            .collect::<HashMap<_, _>>();
+
+        WeightedSelection {
+            selection: Selection {
+                faces: faces_incident_to_vertices(weights.keys().copied(), mesh_graph),
+                ..Default::default()
+            },
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
+
+/// Returns `true` if nothing is between the point and the camera.
+fn is_visible(mesh_graph: &MeshGraph, point: Vec3, region: &ScreenRegion) -> bool {
+    // start a bit away from the point so the faces around it aren't hit
+    const OFFSET: f32 = 1e-4;
+
+    let ray = Ray {
+        origin: point,
+        direction: region.camera_position - point,
+    };
+
+    let ray = Ray {
+        origin: ray.point_at(OFFSET),
+        ..ray
+    };
+
+    mesh_graph
+        .cast_local_ray(&ray.into(), 1.0 - OFFSET, true)
+        .is_none()
+}
@@ -1,58 +1,82 @@
-use glam::Vec3;
 use hashbrown::HashSet;
 
-use mesh_graph::{Face, MeshGraph, Selection};
+use mesh_graph::{MeshGraph, Selection};
 use tracing::{error, instrument};
 
+use crate::{ray::FaceIntersection, utils::face_area_normal};
+
 use super::{
-    DistanceCalculator, FalloffFn, L2, MeshSelector, WeightedSelection, faces_incident_to_vertices,
-    get_sphere_with_falloff_weight_callback,
+    DistanceCalculator, FalloffCurve, L2, MeshSelector, SelectionInput, SelectionWeights,
+    WeightedSelection, faces_incident_to_vertices, get_sphere_with_falloff_weight_callback,
 };
 
 /// Generates a selection on the surface of a mesh that is within a sphere with a falloff and that
 /// is limited to be connected to the input face.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SurfaceMetricWithFalloff<D: DistanceCalculator + Copy + 'static> {
     /// The radius of the sphere.
     pub radius: f32,
 
     /// The falloff distance of the sphere. This means that the influence
     /// decreases from the radius to the radius + falloff.
-    /// The way the influence decreases is controlled by `falloff_func`.
+    /// The way the influence decreases is controlled by `falloff_curve`.
     pub falloff: f32,
 
     /// The metric squared used to calculate the distance between the input position and the vertices.
     pub metric_squared: D,
 
-    /// The falloff function used to calculate the weight of the selection.
-    /// It receives values from 0.0 to 1.0 and has to return a value in the same range.
-    /// Simply returning the input value is a linear falloff.
-    pub falloff_func: FalloffFn,
+    /// The falloff curve used to calculate the weight of the selection.
+    pub falloff_curve: FalloffCurve,
+}
+
+impl<D: DistanceCalculator + Copy> SurfaceMetricWithFalloff<D> {
+    /// Creates a new `SurfaceMetricWithFalloff` selector with the given metric, e.g. [`Ellipsoid`](super::Ellipsoid) or [`Capsule`](super::Capsule).
+    #[inline]
+    pub fn new(
+        radius: f32,
+        falloff: f32,
+        metric_squared: D,
+        falloff_curve: impl Into<FalloffCurve>,
+    ) -> Self {
+        Self {
+            radius,
+            falloff,
+            metric_squared,
+            falloff_curve: falloff_curve.into(),
+        }
+    }
 }
 
 impl SurfaceMetricWithFalloff<L2> {
     #[inline]
-    pub fn sphere(radius: f32, falloff: f32, falloff_func: FalloffFn) -> Self {
+    pub fn sphere(radius: f32, falloff: f32, falloff_curve: impl Into<FalloffCurve>) -> Self {
         Self {
             radius,
             falloff,
             metric_squared: L2,
-            falloff_func,
+            falloff_curve: falloff_curve.into(),
         }
     }
 }
 
 impl<D: DistanceCalculator + Copy + 'static> MeshSelector for SurfaceMetricWithFalloff<D> {
     #[instrument(skip(self, mesh_graph))]
-    fn select(
-        &self,
-        mesh_graph: &MeshGraph,
-        input_pos: Vec3,
-        input_face: Face,
-    ) -> WeightedSelection {
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        let Some(FaceIntersection {
+            point: input_pos,
+            face: input_face,
+        }) = input.surface
+        else {
+            return WeightedSelection::default();
+        };
+
         let sum = self.radius + self.falloff;
         let max_dist_sqr = sum * sum;
 
+        let metric_squared = self
+            .metric_squared
+            .at_surface(face_area_normal(input_face.id, mesh_graph).unwrap_or_default());
+
         let mut vertices = HashSet::new();
         let mut new_vertices = HashSet::new();
 
@@ -75,7 +99,7 @@ impl<D: DistanceCalculator + Copy + 'static> MeshSelector for SurfaceMetricWithF
                 };
 
                 if !vertices.contains(&v_id)
-                    && self.metric_squared.distance_squared(pos, input_pos) <= max_dist_sqr
+                    && metric_squared.distance_squared(input_pos, pos) <= max_dist_sqr
                 {
                     let vertex = match mesh_graph.vertices.get(v_id) {
                         Some(vertex) => vertex,
@@ -109,13 +133,13 @@ impl<D: DistanceCalculator + Copy + 'static> MeshSelector for SurfaceMetricWithF
                 faces: faces_incident_to_vertices(vertices, mesh_graph),
                 ..Default::default()
             },
-            get_weight: get_sphere_with_falloff_weight_callback(
+            weights: SelectionWeights::Callback(get_sphere_with_falloff_weight_callback(
                 input_pos,
                 self.radius,
                 self.falloff,
-                self.falloff_func,
-                self.metric_squared,
-            ),
+                self.falloff_curve.clone(),
+                metric_squared,
+            )),
         }
     }
 }
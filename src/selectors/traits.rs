@@ -1,24 +1,193 @@
 use glam::Vec3;
+use hashbrown::HashMap;
 
-use mesh_graph::{Face, MeshGraph, Selection};
+use mesh_graph::{MeshGraph, Selection, VertexId};
+
+use crate::{attributes::MeshAttributes, ray::FaceIntersection};
+
+use super::ScreenRegion;
 
 /// Trait for selecting a part of the mesh graph for deformation fields to be applied to.
 pub trait MeshSelector {
-    fn select(
-        &self,
-        mesh_graph: &MeshGraph,
-        input_pos: Vec3,
-        input_face: Face,
-    ) -> WeightedSelection;
+    /// Selects a part of the mesh graph. Selectors that require an input that is not given return an empty selection.
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection;
+}
+
+/// The input of [`MeshSelector::select`].
+#[derive(Clone, Default)]
+pub struct SelectionInput<'a> {
+    /// Where the pointer hits the mesh. This is required by the brush selectors like
+    /// [`MetricWithFalloff`](super::MetricWithFalloff).
+    pub surface: Option<FaceIntersection>,
+
+    /// A region on the screen. This is required by [`ScreenSpace`](super::ScreenSpace).
+    pub screen: Option<ScreenRegion>,
+
+    /// The attributes of the mesh graph. This is required by selectors that use face sets like
+    /// [`ComponentWithFalloff`](super::ComponentWithFalloff).
+    pub attributes: Option<&'a MeshAttributes>,
+}
+
+impl<'a> SelectionInput<'a> {
+    /// Adds the attributes of the mesh graph to the input.
+    #[inline]
+    pub fn with_attributes(mut self, attributes: &'a MeshAttributes) -> Self {
+        self.attributes = Some(attributes);
+        self
+    }
+}
+
+// the attributes can be huge, so they are left out when the input is logged
+impl std::fmt::Debug for SelectionInput<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SelectionInput")
+            .field("surface", &self.surface)
+            .field("screen", &self.screen)
+            .field("has_attributes", &self.attributes.is_some())
+            .finish()
+    }
+}
+
+impl From<FaceIntersection> for SelectionInput<'_> {
+    fn from(face_intersection: FaceIntersection) -> Self {
+        Self {
+            surface: Some(face_intersection),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ScreenRegion> for SelectionInput<'_> {
+    fn from(screen_region: ScreenRegion) -> Self {
+        Self {
+            screen: Some(screen_region),
+            ..Default::default()
+        }
+    }
 }
 
 /// Returned by the `MeshSelector::select` method. Represents a mesh selection with associated weights per vertex.
+#[derive(Default)]
 pub struct WeightedSelection {
     pub selection: Selection,
-    pub get_weight: Box<dyn Fn(Vec3) -> f32>,
+    pub weights: SelectionWeights,
+}
+
+impl WeightedSelection {
+    /// Creates a new weighted selection whose weights are computed from the vertex positions by `get_weight`.
+    pub fn from_callback(selection: Selection, get_weight: impl Fn(Vec3) -> f32 + 'static) -> Self {
+        Self {
+            selection,
+            weights: SelectionWeights::Callback(Box::new(get_weight)),
+        }
+    }
+
+    /// Converts the weights to [`SelectionWeights::PerVertex`] by evaluating them at the current
+    /// positions of the selected vertices.
+    pub fn into_per_vertex(self, mesh_graph: &MeshGraph) -> Self {
+        let weights = self.weights.to_per_vertex(mesh_graph, &self.selection);
+
+        Self {
+            selection: self.selection,
+            weights: SelectionWeights::PerVertex(weights),
+        }
+    }
+}
+
+/// The weights of a [`WeightedSelection`] that control how much every vertex is deformed.
+pub enum SelectionWeights {
+    /// The weight is computed from the current position of a vertex.
+    ///
+    /// This changes when the vertex is moved while deforming.
+    Callback(Box<dyn Fn(Vec3) -> f32>),
+
+    /// Every vertex has a fixed weight. Vertices that are not in the map have a weight of zero.
+    ///
+    /// [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes)
+    /// interpolates the weights when edges are split and merges them when edges are collapsed.
+    PerVertex(HashMap<VertexId, f32>),
+}
+
+impl Default for SelectionWeights {
+    fn default() -> Self {
+        Self::Callback(Box::new(|_| 1.0))
+    }
+}
+
+impl SelectionWeights {
+    /// Returns the weight of the vertex which is currently at `position`.
+    #[inline]
+    pub fn weight(&self, vertex_id: VertexId, position: Vec3) -> f32 {
+        match self {
+            Self::Callback(get_weight) => get_weight(position),
+            Self::PerVertex(weights) => weights.get(&vertex_id).copied().unwrap_or_default(),
+        }
+    }
+
+    /// Evaluates the weights of all vertices of `selection` at their current positions.
+    pub fn to_per_vertex(
+        &self,
+        mesh_graph: &MeshGraph,
+        selection: &Selection,
+    ) -> HashMap<VertexId, f32> {
+        selection
+            .resolve_to_vertices(mesh_graph)
+            .into_iter()
+            .filter_map(|vertex_id| {
+                let position = *mesh_graph.positions.get(vertex_id)?;
+                Some((vertex_id, self.weight(vertex_id, position)))
+            })
+            .collect()
+    }
+
+    /// Returns the per-vertex weights if there are any. These need to be kept up to date when
+    /// the topology of the mesh changes.
+    #[inline]
+    pub(crate) fn per_vertex_mut(&mut self) -> Option<&mut HashMap<VertexId, f32>> {
+        match self {
+            Self::Callback(_) => None,
+            Self::PerVertex(weights) => Some(weights),
+        }
+    }
+}
+
+impl From<HashMap<VertexId, f32>> for SelectionWeights {
+    fn from(weights: HashMap<VertexId, f32>) -> Self {
+        Self::PerVertex(weights)
+    }
+}
+
+/// Adapter that turns the weights of any selector into [`SelectionWeights::PerVertex`].
+///
+/// The weights of the wrapped selector are evaluated once at selection time, so they
+/// don't change while the vertices are moved during
+/// [`DeformationField::apply_with_attributes`](crate::deformation::DeformationField::apply_with_attributes).
+#[derive(Debug, Clone, Copy)]
+pub struct PerVertexWeights<S: MeshSelector>(pub S);
+
+impl<S: MeshSelector> MeshSelector for PerVertexWeights<S> {
+    fn select(&self, mesh_graph: &MeshGraph, input: &SelectionInput) -> WeightedSelection {
+        self.0.select(mesh_graph, input).into_per_vertex(mesh_graph)
+    }
 }
 
 /// Used to calculate the distance squared between two points to determine the inclusion and weight of a vertex on the selection.
 pub trait DistanceCalculator {
+    /// The distance squared from the input position `a` to the vertex position `b`.
     fn distance_squared(&self, a: Vec3, b: Vec3) -> f32;
+
+    /// Called by the selectors with the surface normal at the input position before any distances are calculated.
+    /// Returns the distance calculator to use for this selection.
+    fn at_surface(&self, _normal: Vec3) -> Self
+    where
+        Self: Copy,
+    {
+        *self
+    }
+
+    /// The half extents of the box around the input position that contains all points with a distance of at most
+    /// `distance`. This is used to query the BVH.
+    fn half_extents(&self, distance: f32) -> Vec3 {
+        Vec3::splat(distance)
+    }
 }
@@ -0,0 +1,131 @@
+use glam::{Affine3A, Mat4, Vec3};
+
+use crate::ray::Ray;
+
+/// The transform (model matrix) of a sculpted object that maps the local space of its mesh graph to world space.
+///
+/// Mesh graphs, selectors and deformation fields all work in local space. Use this to convert
+/// pointer rays, pointer movements and brush sizes from world space before passing them on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MeshTransform {
+    world_from_local: Affine3A,
+    local_from_world: Affine3A,
+}
+
+impl Default for MeshTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl MeshTransform {
+    /// The transform of an object whose local space is the world space.
+    pub const IDENTITY: Self = Self {
+        world_from_local: Affine3A::IDENTITY,
+        local_from_world: Affine3A::IDENTITY,
+    };
+
+    /// Creates a new transform from the affine transform that maps local space to world space.
+    pub fn new(world_from_local: Affine3A) -> Self {
+        Self {
+            world_from_local,
+            local_from_world: world_from_local.inverse(),
+        }
+    }
+
+    /// Creates a new transform from a model matrix. The matrix has to be affine.
+    pub fn from_mat4(world_from_local: Mat4) -> Self {
+        Self::new(Affine3A::from_mat4(world_from_local))
+    }
+
+    /// The affine transform that maps local space to world space.
+    #[inline]
+    pub fn world_from_local(&self) -> Affine3A {
+        self.world_from_local
+    }
+
+    /// The affine transform that maps world space to local space.
+    #[inline]
+    pub fn local_from_world(&self) -> Affine3A {
+        self.local_from_world
+    }
+
+    /// Converts a point from world space to local space.
+    #[inline]
+    pub fn point_to_local(&self, point: Vec3) -> Vec3 {
+        self.local_from_world.transform_point3(point)
+    }
+
+    /// Converts a point from local space to world space.
+    #[inline]
+    pub fn point_to_world(&self, point: Vec3) -> Vec3 {
+        self.world_from_local.transform_point3(point)
+    }
+
+    /// Converts a vector like a pointer translation from world space to local space.
+    #[inline]
+    pub fn vector_to_local(&self, vector: Vec3) -> Vec3 {
+        self.local_from_world.transform_vector3(vector)
+    }
+
+    /// Converts a vector like a vertex movement from local space to world space.
+    #[inline]
+    pub fn vector_to_world(&self, vector: Vec3) -> Vec3 {
+        self.world_from_local.transform_vector3(vector)
+    }
+
+    /// Converts a normal from local space to world space. The result is normalized.
+    #[inline]
+    pub fn normal_to_world(&self, normal: Vec3) -> Vec3 {
+        self.local_from_world
+            .matrix3
+            .transpose()
+            .mul_vec3a(normal.into())
+            .normalize_or_zero()
+            .into()
+    }
+
+    /// Converts a ray from world space to local space.
+    ///
+    /// The direction is not normalized again, so a point at time of impact `toi` on the local ray
+    /// corresponds to the point at the same `toi` on the world ray.
+    #[inline]
+    pub fn ray_to_local(&self, ray: Ray) -> Ray {
+        Ray {
+            origin: self.point_to_local(ray.origin),
+            direction: self.vector_to_local(ray.direction),
+        }
+    }
+
+    /// Converts a length like a brush radius or an edge length from world space to local space.
+    ///
+    /// For non-uniform scaling this uses the average scale, so it is only an approximation.
+    #[inline]
+    pub fn length_to_local(&self, length: f32) -> f32 {
+        length / self.scale()
+    }
+
+    /// Converts a length from local space to world space. See [`MeshTransform::length_to_local`].
+    #[inline]
+    pub fn length_to_world(&self, length: f32) -> f32 {
+        length * self.scale()
+    }
+
+    /// The average scale factor from local space to world space.
+    pub fn scale(&self) -> f32 {
+        self.world_from_local.matrix3.determinant().abs().cbrt()
+    }
+}
+
+impl From<Affine3A> for MeshTransform {
+    fn from(world_from_local: Affine3A) -> Self {
+        Self::new(world_from_local)
+    }
+}
+
+impl From<Mat4> for MeshTransform {
+    fn from(world_from_local: Mat4) -> Self {
+        Self::from_mat4(world_from_local)
+    }
+}
@@ -0,0 +1,76 @@
+use glam::Vec3;
+use mesh_graph::{FaceId, MeshGraph, VertexId, error_none};
+use parry3d::partitioning::{Bvh, BvhWorkspace};
+
+/// Computes the (not normalized) normal of a face. Its length is twice the area of the face.
+pub(crate) fn face_area_normal(face_id: FaceId, mesh_graph: &MeshGraph) -> Option<Vec3> {
+    let face = mesh_graph
+        .faces
+        .get(face_id)
+        .or_else(error_none!("Face not found"))?;
+
+    let mut positions = face.vertices(mesh_graph).filter_map(|v_id| {
+        mesh_graph
+            .positions
+            .get(v_id)
+            .copied()
+            .or_else(error_none!("Vertex position not found"))
+    });
+
+    let a = positions.next()?;
+    let b = positions.next()?;
+    let c = positions.next()?;
+
+    Some((b - a).cross(c - a))
+}
+
+/// Computes the normal of a vertex by averaging the area weighted normals of the incident faces.
+pub(crate) fn vertex_normal(vertex_id: VertexId, mesh_graph: &MeshGraph) -> Option<Vec3> {
+    let vertex = mesh_graph
+        .vertices
+        .get(vertex_id)
+        .or_else(error_none!("Vertex not found"))?;
+
+    let normal = vertex
+        .faces(mesh_graph)
+        .filter_map(|face_id| face_area_normal(face_id, mesh_graph))
+        .sum::<Vec3>();
+
+    normal.try_normalize()
+}
+
+/// Returns `true` if the vertex is on the border of the mesh.
+///
+/// Compared to `Vertex::is_boundary` this also detects halfedges without twins
+/// which happen for open meshes that were created from triangle lists.
+pub(crate) fn is_boundary_vertex(vertex_id: VertexId, mesh_graph: &MeshGraph) -> bool {
+    let Some(vertex) = mesh_graph
+        .vertices
+        .get(vertex_id)
+        .or_else(error_none!("Vertex not found"))
+    else {
+        return false;
+    };
+
+    vertex.outgoing_halfedges(mesh_graph).any(|he_id| {
+        let Some(he) = mesh_graph.halfedges.get(he_id) else {
+            return true;
+        };
+
+        he.is_boundary()
+            || he
+                .twin
+                .and_then(|twin_id| mesh_graph.halfedges.get(twin_id))
+                .is_none_or(|twin| twin.is_boundary())
+    })
+}
+
+/// Replaces the BVH of the mesh graph by a freshly built one.
+///
+/// This is used after operations that change large parts of the mesh. `MeshGraph::rebuild_bvh`
+/// can't be used for this because it reuses the existing tree which fails after faces have been removed.
+pub(crate) fn rebuild_bvh_from_scratch(mesh_graph: &mut MeshGraph) {
+    mesh_graph.bvh = Bvh::new();
+    mesh_graph.bvh_workspace = BvhWorkspace::default();
+    mesh_graph.rebuild_bvh();
+}